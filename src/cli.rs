@@ -0,0 +1,1100 @@
+use crate::{
+    complete_with_subtasks,
+    config::Config,
+    dates::{format_datetime, parse_reminder_time, ReminderTime},
+    display_message, display_next_occurrence,
+    error::{Error, Result},
+    export::{self, ExportFormat},
+    import::{self, ColumnMapping, ImportFormat, ImportOutcome, DEFAULT_IMPORT_BOARD},
+    journal::DEFAULT_OPERATIONS_LISTED,
+    kanban, list_history, list_operations,
+    notify::{watch, DEFAULT_SNOOZE},
+    recurrence::Recurrence,
+    redo_operations,
+    report::{self, ReportFormat},
+    search::{display_search_results, DEFAULT_SEARCH_LIMIT},
+    set_task_column, set_task_done,
+    store::{NewTask, TaskFilter, TaskSort, TaskStore},
+    tags::{validate_tag_name, TagFilter},
+    undo_operations,
+    user_input::{parse_date, parse_user_date},
+    Color, Priority, Reminder,
+};
+use chrono::{DateTime, Utc};
+use clap::{Args, Parser, Subcommand};
+use std::{io, path::PathBuf};
+use tabled::{Style, Table};
+
+///Command line arguments. Without a subcommand the interactive menu is used
+#[derive(Debug, Parser)]
+#[command(name = env!("CARGO_PKG_NAME"), version, about)]
+pub struct Cli {
+    /// Database file, overriding TASK_MANAGER_DB and the config file
+    #[arg(long, global = true, value_name = "PATH")]
+    pub db: Option<PathBuf>,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Manage tasks
+    Task {
+        #[command(subcommand)]
+        action: TaskCommand,
+    },
+    /// Manage boards
+    Board {
+        #[command(subcommand)]
+        action: BoardCommand,
+    },
+    /// Manage the columns of a board
+    Column {
+        #[command(subcommand)]
+        action: ColumnCommand,
+    },
+    /// Manage comments of a task
+    Comment {
+        #[command(subcommand)]
+        action: CommentCommand,
+    },
+    /// Manage tags
+    Tag {
+        #[command(subcommand)]
+        action: TagCommand,
+    },
+    /// Manage reminders of a task
+    Reminder {
+        #[command(subcommand)]
+        action: ReminderCommand,
+    },
+    /// Search task titles and comments
+    Search {
+        text: String,
+        #[arg(long, default_value_t = DEFAULT_SEARCH_LIMIT)]
+        limit: u32,
+    },
+    /// Report rows left behind by deleted boards or tasks
+    Check {
+        /// Delete the reported rows
+        #[arg(long)]
+        repair: bool,
+    },
+    /// Notify reminders as they come due, see [notifications] in the config file
+    Watch {
+        /// Check once and exit, e.g. from cron
+        #[arg(long)]
+        once: bool,
+        /// Seconds between two checks, overriding the config file
+        #[arg(long)]
+        interval: Option<u64>,
+    },
+    /// Export boards with their tasks, comments and reminders
+    Export {
+        #[arg(long, value_enum, default_value_t)]
+        format: ExportFormat,
+        /// File for JSON, standard output by default. Directory for CSV, one file per table
+        #[arg(long, short, value_name = "PATH")]
+        output: Option<PathBuf>,
+        #[command(flatten)]
+        filter: TaskListArgs,
+    },
+    /// Import tasks from a JSON export, a CSV file or a todo.txt file
+    Import {
+        path: PathBuf,
+        /// Guessed from the file extension by default, .txt being todo.txt
+        #[arg(long, value_enum)]
+        format: Option<ImportFormat>,
+        /// Board of the tasks that don't name one, created if needed
+        #[arg(long, default_value = DEFAULT_IMPORT_BOARD)]
+        board: String,
+        /// CSV header of each field, e.g. "title=Summary,due=Deadline"
+        #[arg(long, value_name = "MAPPING")]
+        map: Option<ColumnMapping>,
+        /// Report what would be imported without saving anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Revert the latest changes, one command or menu action at a time
+    Undo {
+        #[arg(default_value_t = 1)]
+        count: u32,
+    },
+    /// Apply again changes reverted by undo
+    Redo {
+        #[arg(default_value_t = 1)]
+        count: u32,
+    },
+    /// List the latest operations that can be undone or redone
+    Journal {
+        #[arg(long, default_value_t = DEFAULT_OPERATIONS_LISTED)]
+        limit: u32,
+    },
+    /// Status report of the tasks grouped by board, with their comments
+    Report {
+        #[arg(long, value_enum, default_value_t)]
+        format: ReportFormat,
+        /// File to write, standard output by default
+        #[arg(long, short, value_name = "PATH")]
+        output: Option<PathBuf>,
+        /// Only these board ids
+        #[arg(long)]
+        board: Vec<u16>,
+        /// Only pending tasks created and done tasks completed on or after this date
+        #[arg(long, value_parser = parse_date)]
+        from: Option<DateTime<Utc>>,
+        /// Only pending tasks created and done tasks completed before this date
+        #[arg(long, value_parser = parse_date)]
+        to: Option<DateTime<Utc>>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum TaskCommand {
+    /// Create a task
+    Add {
+        title: String,
+        /// Board id, the parent's board for a subtask
+        #[arg(long, required_unless_present = "parent")]
+        board: Option<u16>,
+        /// Create it as a subtask of this task id
+        #[arg(long)]
+        parent: Option<u16>,
+        /// Due date, e.g. "tomorrow 9am", "in 3 days", "20261101 14:30"
+        #[arg(long)]
+        due: Option<String>,
+        /// Reminder, repeatable, e.g. "1h before", "at due", "tomorrow 9am"
+        #[arg(long, value_name = "WHEN")]
+        remind: Vec<String>,
+        #[arg(long, value_enum, default_value_t)]
+        priority: Priority,
+        /// Recurrence, e.g. "daily", "every 2 weeks on mon,fri", "monthly on 15" or an RRULE
+        #[arg(long, value_name = "RULE")]
+        repeat: Option<Recurrence>,
+        /// Tag, repeatable
+        #[arg(long = "tag", value_name = "NAME", value_parser = validate_tag_name)]
+        tags: Vec<String>,
+    },
+    /// List tasks
+    List(TaskListArgs),
+    /// List pending tasks not blocked by a pending task, same as `list --ready`
+    Ready(TaskListArgs),
+    /// Mark a task as done
+    Done {
+        id: u16,
+        /// Mark its open subtasks as done too
+        #[arg(long)]
+        with_subtasks: bool,
+    },
+    /// Mark a task as pending
+    Undone { id: u16 },
+    /// Move a task to a column of its board
+    Status {
+        id: u16,
+        /// Column title, case insensitive
+        column: String,
+    },
+    /// Set or clear the due date of a task
+    Due {
+        id: u16,
+        /// Due date, e.g. "tomorrow 9am", "in 3 days", "20261101 14:30"
+        #[arg(required_unless_present = "clear", conflicts_with = "clear")]
+        date: Option<String>,
+        /// Remove the due date, and the reminders relative to it
+        #[arg(long)]
+        clear: bool,
+    },
+    /// Change the title, board, priority, recurrence or parent of a task
+    Edit {
+        id: u16,
+        #[arg(long)]
+        title: Option<String>,
+        /// Board id
+        #[arg(long)]
+        board: Option<u16>,
+        #[arg(long, value_enum)]
+        priority: Option<Priority>,
+        /// Recurrence, e.g. "daily", "every 2 weeks on mon,fri", "monthly on 15" or an RRULE
+        #[arg(long, value_name = "RULE", conflicts_with = "no_repeat")]
+        repeat: Option<Recurrence>,
+        /// Stop the recurrence
+        #[arg(long)]
+        no_repeat: bool,
+        /// Make it a subtask of this task id
+        #[arg(long, conflicts_with = "no_parent")]
+        parent: Option<u16>,
+        /// Make it a top level task
+        #[arg(long)]
+        no_parent: bool,
+    },
+    /// Move the pending tasks of a board to another board
+    Move {
+        /// Board id to move tasks from
+        #[arg(long)]
+        from: u16,
+        /// Board id to move tasks to
+        #[arg(long)]
+        to: u16,
+        /// Move done tasks too
+        #[arg(long)]
+        include_done: bool,
+    },
+    /// Remind a task again later
+    Snooze {
+        id: u16,
+        /// When to remind again, e.g. "+1h" or "tomorrow 9am"
+        #[arg(default_value = DEFAULT_SNOOZE)]
+        until: String,
+    },
+    /// Add tags to a task
+    Tag {
+        id: u16,
+        #[arg(required = true, value_parser = validate_tag_name)]
+        names: Vec<String>,
+    },
+    /// Remove tags from a task
+    Untag {
+        id: u16,
+        #[arg(required = true)]
+        names: Vec<String>,
+    },
+    /// Make a task wait until another one is done
+    Block {
+        id: u16,
+        /// Id of the task that must be done first
+        #[arg(long)]
+        by: u16,
+    },
+    /// Remove a dependency added with `block`
+    Unblock {
+        id: u16,
+        #[arg(long)]
+        by: u16,
+    },
+    /// List the tasks blocking a task
+    Blockers { id: u16 },
+    /// Show the changes of a task, deleted ones included
+    History { id: u16 },
+    /// Delete a task and its comments
+    Delete { id: u16 },
+}
+
+#[derive(Debug, Args)]
+pub struct TaskListArgs {
+    /// Only pending tasks
+    #[arg(long, conflicts_with = "done")]
+    pub pending: bool,
+    /// Only done tasks
+    #[arg(long)]
+    pub done: bool,
+    /// Only tasks from these board ids
+    #[arg(long)]
+    pub board: Vec<u16>,
+    /// Only pending tasks not blocked by a pending task
+    #[arg(long, conflicts_with = "done")]
+    pub ready: bool,
+    /// Only subtasks of this task id
+    #[arg(long)]
+    pub parent: Option<u16>,
+    /// Only tasks matching this tag expression, e.g. "+work -blocked"
+    #[arg(long, allow_hyphen_values = true, value_name = "EXPR")]
+    pub tags: Option<TagFilter>,
+    /// Only tasks whose title contains this text
+    #[arg(long)]
+    pub text: Option<String>,
+    /// Only tasks due before this date
+    #[arg(long, value_parser = parse_date, conflicts_with = "overdue")]
+    pub due_before: Option<DateTime<Utc>>,
+    /// Only tasks due after this date
+    #[arg(long, value_parser = parse_date)]
+    pub due_after: Option<DateTime<Utc>>,
+    /// Only pending tasks past their due date
+    #[arg(long, conflicts_with = "done")]
+    pub overdue: bool,
+    /// Only tasks created on or after this date
+    #[arg(long, value_parser = parse_date)]
+    pub created_after: Option<DateTime<Utc>>,
+    /// Only tasks created before this date
+    #[arg(long, value_parser = parse_date)]
+    pub created_before: Option<DateTime<Utc>>,
+    /// Only tasks completed on or after this date
+    #[arg(long, value_parser = parse_date)]
+    pub completed_after: Option<DateTime<Utc>>,
+    /// Only tasks completed before this date
+    #[arg(long, value_parser = parse_date)]
+    pub completed_before: Option<DateTime<Utc>>,
+    #[arg(long, value_enum, default_value_t)]
+    pub sort: TaskSort,
+    /// Sort in descending order
+    #[arg(long)]
+    pub desc: bool,
+    #[arg(long)]
+    pub limit: Option<u32>,
+    #[arg(long)]
+    pub offset: Option<u32>,
+}
+
+impl TaskListArgs {
+    pub fn to_filter(&self) -> TaskFilter {
+        TaskFilter {
+            done: match (self.pending || self.overdue || self.ready, self.done) {
+                (true, _) => Some(false),
+                (_, true) => Some(true),
+                _ => None,
+            },
+            board_ids: self.board.clone(),
+            parent_id: self.parent,
+            ready: self.ready,
+            tags: self.tags.clone().unwrap_or_default(),
+            due_before: match self.overdue {
+                true => Some(Utc::now()),
+                false => self.due_before,
+            },
+            due_after: self.due_after,
+            text: self.text.clone(),
+            created_after: self.created_after,
+            created_before: self.created_before,
+            completed_after: self.completed_after,
+            completed_before: self.completed_before,
+            sort: self.sort,
+            descending: self.desc,
+            limit: self.limit,
+            offset: self.offset,
+        }
+    }
+}
+
+#[derive(Debug, Subcommand)]
+pub enum BoardCommand {
+    /// Create a board
+    Add { title: String },
+    /// List boards
+    List,
+    /// Change the title of a board
+    Rename { id: u16, title: String },
+    /// Delete a board and its tasks
+    Delete { id: u16 },
+    /// Show the tasks of a board by column, in full screen
+    View { id: u16 },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ColumnCommand {
+    /// List the columns of a board in order
+    List { board_id: u16 },
+    /// Add a column before the terminal one
+    Add { board_id: u16, title: String },
+    /// Change the title of a column
+    Rename { id: u16, title: String },
+    /// Move a column to a position, 0 being the first
+    Move { id: u16, position: u16 },
+    /// Make tasks in this column done, instead of the current terminal column
+    Terminal { id: u16 },
+    /// Delete a column, its tasks going to the first column
+    Delete { id: u16 },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum CommentCommand {
+    /// Add a comment to a task
+    Add { task_id: u16, title: String },
+    /// List the comments of a task
+    List { task_id: u16 },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum TagCommand {
+    /// List tags with their number of tasks
+    List,
+    /// Change the name of a tag
+    Rename {
+        name: String,
+        #[arg(value_parser = validate_tag_name)]
+        new_name: String,
+    },
+    /// Move the tasks of a tag to another tag and delete the first one
+    Merge { from: String, into: String },
+    /// Delete a tag, untagging its tasks
+    Delete { name: String },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ReminderCommand {
+    /// Add a reminder to a task
+    Add {
+        task_id: u16,
+        /// e.g. "1h before", "2 days before", "at due", "tomorrow 9am"
+        when: String,
+    },
+    /// List the reminders of a task
+    List { task_id: u16 },
+    /// Delete a reminder
+    Delete { id: u16 },
+}
+
+impl Command {
+    ///Whether the changes of the command are journaled as one operation. Read only commands
+    ///aren't, nor the journal commands themselves, watching reminders, repairing orphans whose
+    ///rows can't be restored and the kanban view which journals each move
+    fn is_journaled(&self) -> bool {
+        !matches!(
+            self,
+            Command::Task {
+                action: TaskCommand::List(_)
+                    | TaskCommand::Ready(_)
+                    | TaskCommand::Blockers { .. }
+                    | TaskCommand::History { .. }
+            } | Command::Board {
+                action: BoardCommand::List | BoardCommand::View { .. }
+            } | Command::Column {
+                action: ColumnCommand::List { .. }
+            } | Command::Comment {
+                action: CommentCommand::List { .. }
+            } | Command::Tag {
+                action: TagCommand::List
+            } | Command::Reminder {
+                action: ReminderCommand::List { .. }
+            } | Command::Search { .. }
+                | Command::Check { .. }
+                | Command::Watch { .. }
+                | Command::Export { .. }
+                | Command::Report { .. }
+                | Command::Undo { .. }
+                | Command::Redo { .. }
+                | Command::Journal { .. }
+        )
+    }
+}
+
+pub fn run(store: &dyn TaskStore, command: Command) -> Result<()> {
+    match command.is_journaled() {
+        true => {
+            let mut command = Some(command);
+            store.journaled(&command_line(), &mut || match command.take() {
+                Some(command) => run_command(store, command),
+                None => Ok(()),
+            })
+        }
+        false => run_command(store, command),
+    }
+}
+
+///Arguments without the database option, describing the operation
+fn command_line() -> String {
+    let mut words = Vec::new();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--db" => {
+                args.next();
+            }
+            x if x.starts_with("--db=") => (),
+            _ => words.push(arg),
+        }
+    }
+    words.join(" ")
+}
+
+fn run_command(store: &dyn TaskStore, command: Command) -> Result<()> {
+    match command {
+        Command::Undo { count } => undo_operations(store, count),
+        Command::Redo { count } => redo_operations(store, count),
+        Command::Journal { limit } => list_operations(store, limit),
+        Command::Task { action } => run_task(store, action),
+        Command::Board { action } => run_board(store, action),
+        Command::Column { action } => run_column(store, action),
+        Command::Comment { action } => run_comment(store, action),
+        Command::Tag { action } => run_tag(store, action),
+        Command::Reminder { action } => run_reminder(store, action),
+        Command::Search { text, limit } => search(store, &text, limit),
+        Command::Check { repair } => check_integrity(store, repair),
+        Command::Watch { once, interval } => {
+            let mut config = Config::load()?.notifications;
+            if let Some(interval) = interval {
+                config.interval = interval;
+            }
+            watch(store, &config, once)
+        }
+        Command::Export {
+            format,
+            output,
+            filter,
+        } => export_data(store, format, output, &filter.to_filter()),
+        Command::Import {
+            path,
+            format,
+            board,
+            map,
+            dry_run,
+        } => import_data(store, &path, format, &board, map, dry_run),
+        Command::Report {
+            format,
+            output,
+            board,
+            from,
+            to,
+        } => {
+            let data = report::build(store, &board, from, to)?;
+            let text = match format {
+                ReportFormat::Markdown => report::to_markdown(&data),
+                ReportFormat::Html => report::to_html(&data),
+            };
+            match output {
+                Some(path) => {
+                    std::fs::write(&path, text)?;
+                    display_message(
+                        "ok",
+                        &format!("Report written to {}", path.display()),
+                        Color::Green,
+                    );
+                }
+                None => print!("{}", text),
+            }
+            Ok(())
+        }
+    }
+}
+
+fn import_data(
+    store: &dyn TaskStore,
+    path: &std::path::Path,
+    format: Option<ImportFormat>,
+    board: &str,
+    mapping: Option<ColumnMapping>,
+    dry_run: bool,
+) -> Result<()> {
+    let format = format
+        .or_else(|| ImportFormat::from_path(path))
+        .ok_or_else(|| {
+            Error::Validation(format!(
+                "Unknown format of {}, use --format",
+                path.display()
+            ))
+        })?;
+    let file = std::fs::File::open(path)?;
+    let tasks = match format {
+        ImportFormat::Json => import::read_json(file)?,
+        ImportFormat::Csv => import::read_csv(file, &mapping.unwrap_or_default())?,
+        ImportFormat::Todotxt => import::read_todotxt(file)?,
+    };
+
+    let report = import::import(store, &tasks, board, dry_run)?;
+    if !report.tasks.is_empty() {
+        println!("{}", Table::new(&report.tasks).with(Style::modern()));
+    }
+    let summary = format!(
+        "{} tasks and {} boards, {} duplicates skipped",
+        report.count(ImportOutcome::Created),
+        report.boards.len(),
+        report.count(ImportOutcome::Duplicate)
+    );
+    match dry_run {
+        true => display_message(
+            "info",
+            &format!("Dry run, nothing saved: would create {}", summary),
+            Color::Cyan,
+        ),
+        false => display_message("ok", &format!("Created {}", summary), Color::Green),
+    }
+    Ok(())
+}
+
+fn export_data(
+    store: &dyn TaskStore,
+    format: ExportFormat,
+    output: Option<PathBuf>,
+    filter: &TaskFilter,
+) -> Result<()> {
+    let data = export::collect(store, filter)?;
+    match (format, output) {
+        (ExportFormat::Json, None) => {
+            export::write_json(&data, io::stdout().lock())?;
+            println!();
+        }
+        (ExportFormat::Json, Some(path)) => {
+            export::write_json(&data, std::fs::File::create(&path)?)?;
+            display_message(
+                "ok",
+                &format!("Exported to {}", path.display()),
+                Color::Green,
+            );
+        }
+        (ExportFormat::Csv, None) => {
+            return Err(Error::Validation(
+                "CSV export needs an --output directory".to_string(),
+            ))
+        }
+        (ExportFormat::Csv, Some(directory)) => {
+            for path in export::write_csv(&data, &directory)? {
+                display_message(
+                    "ok",
+                    &format!("Exported to {}", path.display()),
+                    Color::Green,
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+fn run_task(store: &dyn TaskStore, action: TaskCommand) -> Result<()> {
+    match action {
+        TaskCommand::Add {
+            title,
+            board,
+            parent,
+            due,
+            remind,
+            priority,
+            repeat,
+            tags,
+        } => {
+            let board = match (board, parent) {
+                (Some(board), _) => board,
+                (None, Some(parent)) => store.get_task(parent)?.board_id,
+                (None, None) => return Err(Error::Validation("--board is required".to_string())),
+            };
+            store.get_board(board)?;
+            let due_at = match due {
+                Some(date) => Some(parse_user_date(&date, true)?),
+                None => None,
+            };
+            //Every reminder is checked before the task is created
+            let reminders = remind
+                .iter()
+                .map(|when| parse_reminder(when, due_at.is_some()))
+                .collect::<Result<Vec<_>>>()?;
+            //A task whose reminders or tags fail isn't created
+            store.transaction(true, &mut || {
+                let id = store.add_task(&NewTask {
+                    priority,
+                    due_at,
+                    recurrence: repeat.clone(),
+                    parent_id: parent,
+                    ..NewTask::new(&title, board)
+                })?;
+                for reminder in &reminders {
+                    store.add_reminder(id, *reminder)?;
+                }
+                store.set_task_tags(id, &tags)
+            })?;
+            display_message(
+                "ok",
+                &format!("Task {} has been created", title),
+                Color::Green,
+            );
+        }
+        TaskCommand::List(args) => list_tasks(store, &args)?,
+        TaskCommand::Ready(args) => list_tasks(
+            store,
+            &TaskListArgs {
+                ready: true,
+                ..args
+            },
+        )?,
+        TaskCommand::Done { id, with_subtasks } => match with_subtasks {
+            true => {
+                let (completed, next) = complete_with_subtasks(store, id)?;
+                display_message(
+                    "ok",
+                    &format!("{} subtasks have been completed", completed),
+                    Color::Green,
+                );
+                display_status_change(store, id, next)?
+            }
+            false => set_task_status(store, id, true)?,
+        },
+        TaskCommand::Undone { id } => set_task_status(store, id, false)?,
+        TaskCommand::Status { id, column } => {
+            let task = store.get_task(id)?;
+            let column = store
+                .list_columns(task.board_id)?
+                .into_iter()
+                .find(|x| x.title.eq_ignore_ascii_case(&column))
+                .ok_or_else(|| Error::NotFound(format!("Column {}", column)))?;
+            let next = set_task_column(store, id, column.id)?;
+            display_message(
+                "ok",
+                &format!("Task {} moved to {}", id, column.title),
+                Color::Green,
+            );
+            if let Some(next) = next {
+                display_next_occurrence(store, next)?;
+            }
+        }
+        TaskCommand::Due { id, date, clear } => {
+            let mut task = store.get_task(id)?;
+            task.due_at = match (date, clear) {
+                (Some(date), false) => Some(parse_user_date(&date, false)?),
+                _ => None,
+            };
+            store.update_task(&task)?;
+            let message = match &task.due_at {
+                Some(due_at) => format!("Due date set to {}", format_datetime(due_at)),
+                None => format!("Due date of task {} has been cleared", id),
+            };
+            display_message("ok", &message, Color::Green);
+        }
+        TaskCommand::Edit {
+            id,
+            title,
+            board,
+            priority,
+            repeat,
+            no_repeat,
+            parent,
+            no_parent,
+        } => {
+            let mut task = store.get_task(id)?;
+            if parent.is_some() || no_parent {
+                task.parent_id = parent;
+            }
+            if let Some(priority) = priority {
+                task.priority = priority;
+            }
+            if repeat.is_some() || no_repeat {
+                task.recurrence = repeat;
+            }
+            if let Some(title) = title {
+                task.title = title;
+            }
+            if let Some(board_id) = board {
+                store.get_board(board_id)?;
+                task.board_id = board_id;
+            }
+            store.update_task(&task)?;
+            display_message("ok", &format!("Task {} has been updated", id), Color::Green);
+        }
+        TaskCommand::Move {
+            from,
+            to,
+            include_done,
+        } => {
+            store.get_board(from)?;
+            store.get_board(to)?;
+            let filter = match include_done {
+                true => TaskFilter::default(),
+                false => TaskFilter::pending(),
+            };
+            let moved = store.move_tasks(&filter.board(from), to)?;
+            display_message(
+                "ok",
+                &format!("{} tasks moved from board {} to board {}", moved, from, to),
+                Color::Green,
+            );
+        }
+        TaskCommand::Snooze { id, until } => {
+            store.get_task(id)?;
+            let reminder = parse_user_date(&until, true)?;
+            store.add_reminder(id, ReminderTime::At(reminder))?;
+            display_message(
+                "ok",
+                &format!("Task {} snoozed until {}", id, format_datetime(&reminder)),
+                Color::Green,
+            );
+        }
+        TaskCommand::Tag { id, names } => {
+            let mut tags = store.get_task(id)?.tags;
+            tags.extend(names);
+            store.set_task_tags(id, &tags)?;
+            display_message("ok", &format!("Task {} has been tagged", id), Color::Green);
+        }
+        TaskCommand::Untag { id, names } => {
+            let tags: Vec<String> = store
+                .get_task(id)?
+                .tags
+                .into_iter()
+                .filter(|x| !names.iter().any(|name| name.eq_ignore_ascii_case(x)))
+                .collect();
+            store.set_task_tags(id, &tags)?;
+            display_message(
+                "ok",
+                &format!("Task {} has been untagged", id),
+                Color::Green,
+            );
+        }
+        TaskCommand::Block { id, by } => {
+            store.add_dependency(id, by)?;
+            display_message(
+                "ok",
+                &format!("Task {} is now blocked by task {}", id, by),
+                Color::Green,
+            );
+        }
+        TaskCommand::Unblock { id, by } => {
+            store.remove_dependency(id, by)?;
+            display_message(
+                "ok",
+                &format!("Task {} is no longer blocked by task {}", id, by),
+                Color::Green,
+            );
+        }
+        TaskCommand::Blockers { id } => {
+            store.get_task(id)?;
+            let blockers = store.list_blockers(id)?;
+            if blockers.is_empty() {
+                display_message("info", "This task has no blockers", Color::Cyan);
+                return Ok(());
+            }
+            println!("{}", Table::new(blockers).with(Style::modern()));
+        }
+        TaskCommand::History { id } => list_history(store, id)?,
+        TaskCommand::Delete { id } => {
+            store.get_task(id)?;
+            store.delete_task(id)?;
+            display_message("ok", &format!("Task {} has been deleted", id), Color::Green);
+        }
+    };
+    Ok(())
+}
+
+fn run_board(store: &dyn TaskStore, action: BoardCommand) -> Result<()> {
+    match action {
+        BoardCommand::Add { title } => {
+            store.add_board(&title)?;
+            display_message(
+                "ok",
+                &format!("Board {} has been created", title),
+                Color::Green,
+            );
+        }
+        BoardCommand::List => {
+            for board in store.list_boards()? {
+                println!("{} - {}", board.id, board.title);
+            }
+        }
+        BoardCommand::Rename { id, title } => {
+            store.get_board(id)?;
+            store.rename_board(id, &title)?;
+            display_message(
+                "ok",
+                &format!("Board {} has been renamed", id),
+                Color::Green,
+            );
+        }
+        BoardCommand::View { id } => kanban::run(store, id)?,
+        BoardCommand::Delete { id } => {
+            store.get_board(id)?;
+            store.delete_board(id)?;
+            display_message(
+                "ok",
+                &format!("Board {} has been deleted", id),
+                Color::Green,
+            );
+        }
+    };
+    Ok(())
+}
+
+fn run_column(store: &dyn TaskStore, action: ColumnCommand) -> Result<()> {
+    match action {
+        ColumnCommand::List { board_id } => {
+            store.get_board(board_id)?;
+            let columns = store.list_columns(board_id)?;
+            println!("{}", Table::new(columns).with(Style::modern()));
+        }
+        ColumnCommand::Add { board_id, title } => {
+            store.add_column(board_id, &title)?;
+            display_message(
+                "ok",
+                &format!("Column {} has been created", title),
+                Color::Green,
+            );
+        }
+        ColumnCommand::Rename { id, title } => {
+            store.rename_column(id, &title)?;
+            display_message(
+                "ok",
+                &format!("Column {} has been renamed", id),
+                Color::Green,
+            );
+        }
+        ColumnCommand::Move { id, position } => {
+            store.move_column(id, position)?;
+            display_message("ok", &format!("Column {} has been moved", id), Color::Green);
+        }
+        ColumnCommand::Terminal { id } => {
+            store.set_terminal_column(id)?;
+            display_message(
+                "ok",
+                &format!("Column {} is now the terminal column", id),
+                Color::Green,
+            );
+        }
+        ColumnCommand::Delete { id } => {
+            store.delete_column(id)?;
+            display_message(
+                "ok",
+                &format!("Column {} has been deleted", id),
+                Color::Green,
+            );
+        }
+    };
+    Ok(())
+}
+
+fn run_comment(store: &dyn TaskStore, action: CommentCommand) -> Result<()> {
+    match action {
+        CommentCommand::Add { task_id, title } => {
+            store.get_task(task_id)?;
+            store.add_comment(task_id, &title)?;
+            display_message("ok", "Comment has been added", Color::Green);
+        }
+        CommentCommand::List { task_id } => {
+            store.get_task(task_id)?;
+            for comment in store.list_comments(task_id)? {
+                println!(
+                    "{} - {} [{}]",
+                    comment.id,
+                    comment.title,
+                    format_datetime(&comment.created_at)
+                );
+            }
+        }
+    };
+    Ok(())
+}
+
+fn run_tag(store: &dyn TaskStore, action: TagCommand) -> Result<()> {
+    match action {
+        TagCommand::List => {
+            let tags = store.list_tags()?;
+            if tags.is_empty() {
+                display_message("info", "No Tags found", Color::Cyan);
+                return Ok(());
+            }
+            println!("{}", Table::new(tags).with(Style::modern()));
+        }
+        TagCommand::Rename { name, new_name } => {
+            let tag = store.get_tag(&name)?;
+            store.rename_tag(tag.id, &new_name)?;
+            display_message(
+                "ok",
+                &format!("Tag {} has been renamed to {}", tag.name, new_name),
+                Color::Green,
+            );
+        }
+        TagCommand::Merge { from, into } => {
+            let from = store.get_tag(&from)?;
+            let into = store.get_tag(&into)?;
+            store.merge_tags(from.id, into.id)?;
+            display_message(
+                "ok",
+                &format!("Tag {} has been merged into {}", from.name, into.name),
+                Color::Green,
+            );
+        }
+        TagCommand::Delete { name } => {
+            let tag = store.get_tag(&name)?;
+            store.delete_tag(tag.id)?;
+            display_message(
+                "ok",
+                &format!("Tag {} has been deleted", tag.name),
+                Color::Green,
+            );
+        }
+    };
+    Ok(())
+}
+
+fn run_reminder(store: &dyn TaskStore, action: ReminderCommand) -> Result<()> {
+    match action {
+        ReminderCommand::Add { task_id, when } => {
+            let task = store.get_task(task_id)?;
+            let reminder = parse_reminder(&when, task.due_at.is_some())?;
+            store.add_reminder(task_id, reminder)?;
+            display_message("ok", "Reminder has been added", Color::Green);
+        }
+        ReminderCommand::List { task_id } => {
+            store.get_task(task_id)?;
+            let reminders: Vec<Reminder> = store.list_reminders(task_id)?;
+            if reminders.is_empty() {
+                display_message("info", "This task has no reminders", Color::Cyan);
+                return Ok(());
+            }
+            println!("{}", Table::new(&reminders).with(Style::modern()));
+        }
+        ReminderCommand::Delete { id } => {
+            store.delete_reminder(id)?;
+            display_message(
+                "ok",
+                &format!("Reminder {} has been deleted", id),
+                Color::Green,
+            );
+        }
+    };
+    Ok(())
+}
+
+///A reminder relative to the due date needs the task to have one
+fn parse_reminder(when: &str, has_due_date: bool) -> Result<ReminderTime> {
+    let reminder = parse_reminder_time(when)?;
+    match (&reminder, has_due_date) {
+        (ReminderTime::BeforeDue(_), false) => Err(Error::Validation(format!(
+            "'{}' needs a due date, or a date like 'tomorrow 9am'",
+            when
+        ))),
+        _ => Ok(reminder),
+    }
+}
+
+fn search(store: &dyn TaskStore, text: &str, limit: u32) -> Result<()> {
+    let results = store.search(text, limit)?;
+    if results.is_empty() {
+        display_message("info", "No Tasks match this search", Color::Cyan);
+        return Ok(());
+    }
+    display_search_results(&results);
+    Ok(())
+}
+
+fn check_integrity(store: &dyn TaskStore, repair: bool) -> Result<()> {
+    let orphans = store.check_integrity()?;
+    if orphans.is_empty() {
+        display_message("ok", "No orphan rows found", Color::Green);
+        return Ok(());
+    }
+
+    println!("{}", Table::new(&orphans).with(Style::modern()));
+
+    if repair {
+        let repaired = store.repair_orphans()?;
+        display_message(
+            "ok",
+            &format!("{} orphan rows have been deleted", repaired),
+            Color::Green,
+        );
+    } else {
+        display_message(
+            "info",
+            "Run with --repair to delete these rows",
+            Color::Yellow,
+        );
+    }
+    Ok(())
+}
+
+fn list_tasks(store: &dyn TaskStore, args: &TaskListArgs) -> Result<()> {
+    let tasks = store.list_tasks(&args.to_filter())?;
+    if tasks.is_empty() {
+        display_message("info", "No Tasks found", Color::Cyan);
+        return Ok(());
+    }
+
+    let table = Table::new(tasks).with(Style::modern());
+    println!("{}", table);
+    Ok(())
+}
+
+fn set_task_status(store: &dyn TaskStore, id: u16, done: bool) -> Result<()> {
+    let next = set_task_done(store, id, done)?;
+    display_status_change(store, id, next)
+}
+
+fn display_status_change(store: &dyn TaskStore, id: u16, next: Option<u16>) -> Result<()> {
+    display_message("ok", &format!("Task {} has been updated", id), Color::Green);
+    if let Some(next) = next {
+        display_next_occurrence(store, next)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,126 @@
+use crate::error::{Error, Result};
+use serde::Deserialize;
+use std::{env, fmt, fs, path::PathBuf};
+
+pub const DATABASE_ENV_VAR: &str = "TASK_MANAGER_DB";
+pub const CONFIG_FILE_NAME: &str = "config.toml";
+
+///Settings read from the config file
+#[derive(Debug, Default, Deserialize)]
+pub struct Config {
+    pub database: Option<PathBuf>,
+    #[serde(default)]
+    pub notifications: NotificationConfig,
+}
+
+///[notifications] section, how the watch mode announces due reminders
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct NotificationConfig {
+    ///Desktop notification through notify-send
+    pub desktop: bool,
+    ///Terminal bell
+    pub bell: bool,
+    ///Shell command run for each reminder, with TASK_ID, TASK_TITLE and TASK_DUE set
+    pub command: Option<String>,
+    ///Seconds between two checks of the database
+    pub interval: u64,
+}
+
+impl Default for NotificationConfig {
+    fn default() -> NotificationConfig {
+        NotificationConfig {
+            desktop: true,
+            bell: true,
+            command: None,
+            interval: 60,
+        }
+    }
+}
+
+///Where the database path came from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DatabaseSource {
+    Flag,
+    Env,
+    ConfigFile(PathBuf),
+    Default,
+}
+
+#[derive(Debug, Clone)]
+pub struct DatabaseLocation {
+    pub path: PathBuf,
+    pub source: DatabaseSource,
+}
+
+impl fmt::Display for DatabaseSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DatabaseSource::Flag => write!(f, "--db flag"),
+            DatabaseSource::Env => write!(f, "{} environment variable", DATABASE_ENV_VAR),
+            DatabaseSource::ConfigFile(path) => write!(f, "config file {}", path.display()),
+            DatabaseSource::Default => write!(f, "default location"),
+        }
+    }
+}
+
+///$XDG_CONFIG_HOME/task-manager/config.toml
+pub fn get_config_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|x| x.join(env!("CARGO_PKG_NAME")).join(CONFIG_FILE_NAME))
+}
+
+///$XDG_DATA_HOME/task-manager/task-manager.db3, or the current directory
+pub fn get_default_database_path() -> PathBuf {
+    let file_name = format!("{}.db3", env!("CARGO_PKG_NAME"));
+    match dirs::data_dir() {
+        Some(dir) => dir.join(env!("CARGO_PKG_NAME")).join(file_name),
+        None => PathBuf::from(file_name),
+    }
+}
+
+impl Config {
+    ///Read the config file, an absent file being an empty config
+    pub fn load() -> Result<Config> {
+        let path = match get_config_path() {
+            Some(path) if path.exists() => path,
+            _ => return Ok(Config::default()),
+        };
+        let content = fs::read_to_string(&path)?;
+        toml::from_str(&content).map_err(|e| {
+            Error::Validation(format!("Invalid config file {}: {}", path.display(), e))
+        })
+    }
+}
+
+///Resolve the database path from the --db flag, the environment, the config file and the default, in that order
+pub fn resolve_database_location(flag: Option<PathBuf>) -> Result<DatabaseLocation> {
+    let location = match flag {
+        Some(path) => DatabaseLocation {
+            path,
+            source: DatabaseSource::Flag,
+        },
+        None => match env::var_os(DATABASE_ENV_VAR).filter(|x| !x.is_empty()) {
+            Some(path) => DatabaseLocation {
+                path: PathBuf::from(path),
+                source: DatabaseSource::Env,
+            },
+            None => match (Config::load()?.database, get_config_path()) {
+                (Some(path), Some(config_path)) => DatabaseLocation {
+                    path,
+                    source: DatabaseSource::ConfigFile(config_path),
+                },
+                _ => DatabaseLocation {
+                    path: get_default_database_path(),
+                    source: DatabaseSource::Default,
+                },
+            },
+        },
+    };
+
+    if location.source == DatabaseSource::Default {
+        if let Some(dir) = location.path.parent().filter(|x| !x.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+    }
+    Ok(location)
+}
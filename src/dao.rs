@@ -1,306 +1,1247 @@
 use crate::{
-    datetime_str_is_past, display_message, get_connection, get_tasks, select_board,
-    user_input::{get_user_confirmation, get_user_date, get_user_input, get_user_selection},
-    Board, Color, Comment, Record, Task, DATETIME_FORMAT, SAMPLE_TITLE, TABLE_BOARDS,
-    TABLE_COMMENTS, TABLE_TASKS,
+    dates::{format_duration, ReminderTime},
+    error::{Error, Result},
+    history::{
+        status_name, HistoryAction, HistoryEntry, FIELD_BOARD, FIELD_COLUMN, FIELD_DUE,
+        FIELD_PARENT, FIELD_PRIORITY, FIELD_RECURRENCE, FIELD_REMINDER, FIELD_STATUS, FIELD_TAGS,
+        FIELD_TITLE,
+    },
+    journal::{self, Operation, JOURNAL_LIMIT, TABLE_RECORDING},
+    migrations,
+    search::{
+        to_fts_query, SearchResult, HIGHLIGHT_END, HIGHLIGHT_START, SNIPPET_ELLIPSIS,
+        SNIPPET_TOKENS,
+    },
+    store::{NewTask, TaskFilter, TaskStore},
+    tags::Tag,
+    Board, Column, Comment, Orphan, Progress, Reminder, Task, DEFAULT_COLUMNS, TABLE_BOARDS,
+    TABLE_COLUMNS, TABLE_COMMENTS, TABLE_COMMENTS_SEARCH, TABLE_DEPENDENCIES, TABLE_HISTORY,
+    TABLE_JOURNAL, TABLE_OPERATIONS, TABLE_REMINDERS, TABLE_TAGS, TABLE_TASKS, TABLE_TASKS_SEARCH,
+    TABLE_TASK_TAGS,
 };
-use chrono::Utc;
-use rusqlite::{params, Result};
-use tabled::{Disable, Style, Table};
-
-pub fn get_records_qtd(table: &str) -> Result<u16> {
-    let conn = get_connection();
-    let query = format!("SELECT COUNT(*) FROM {table}");
-    let mut stmt = conn.prepare(&query)?;
-    let query_result = stmt.query_map([], |row| Ok(Record { qtd: row.get(0)? }))?;
-    let qtd_records = query_result.last().unwrap()?.qtd;
-    Ok(qtd_records)
-}
+use chrono::{DateTime, Duration, SubsecRound, Utc};
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension, Row};
+use std::{ops::Deref, path::Path};
 
-///Create database table if not exists
-pub fn create_database() -> Result<()> {
-    let conn = get_connection();
-
-    //boards
-    conn.execute(
-        &format!(
-            "CREATE TABLE IF NOT EXISTS {TABLE_BOARDS} (
-                  id              INTEGER PRIMARY KEY,
-                  title           VARCHAR(255) NOT NULL
-                  );"
-        ),
-        [],
-    )?;
-
-    //tasks
-    conn.execute(
-        &format!(
-            "CREATE TABLE IF NOT EXISTS {TABLE_TASKS} (
-                  id              INTEGER PRIMARY KEY,
-                  title           VARCHAR(255) NOT NULL,
-                  done              INTEGER NOT NULL,
-                  board_id          INTEGER NOT NULL,
-                  created_at           VARCHAR(255) NOT NULL,
-                  reminder           VARCHAR(255) NOT NULL,
-                  FOREIGN KEY(board_id) REFERENCES {TABLE_BOARDS}(id)
-                );"
-        ),
-        [],
-    )?;
-
-    //comments
-    conn.execute(
-        &format!(
-            "CREATE TABLE IF NOT EXISTS {TABLE_COMMENTS} (
-                  id              INTEGER PRIMARY KEY,
-                  title           VARCHAR(255) NOT NULL,
-                  task_id          INTEGER NOT NULL,
-                  created_at           VARCHAR(255) NOT NULL,
-                  FOREIGN KEY(task_id) REFERENCES {TABLE_TASKS}(id)
-                  );"
-        ),
-        [],
-    )?;
+const TASK_COLUMNS: &str = "id, title, done, board_id, created_at, due_at, priority, recurrence, parent_id, column_id, completed_at";
+const COLUMN_COLUMNS: &str = "id, board_id, title, position, terminal";
+const BOARD_COLUMNS: &str = "id, title";
+const COMMENT_COLUMNS: &str = "id, title, task_id, created_at";
+const REMINDER_COLUMNS: &str = "id, task_id, remind_at, before_due, fired_at";
+const HISTORY_COLUMNS: &str = "id, task_id, created_at, action, field, old_value, new_value";
+const OPERATION_COLUMNS: &str = "id, description, created_at, undone";
+///A match in the task title counts more than one in a comment
+const TITLE_MATCH_WEIGHT: f64 = 2.0;
 
-    Ok(())
+///SQLite implementation of TaskStore over an injected connection
+pub struct SqliteStore {
+    conn: Connection,
 }
 
-pub fn edit_board(title: &str, id: u16) -> Result<()> {
-    let conn = get_connection();
-    let title = get_user_input("New Board title", title, true).unwrap();
+impl SqliteStore {
+    ///Enable foreign keys, bring the schema up to date and start journaling changes
+    pub fn new(mut conn: Connection) -> Result<SqliteStore> {
+        conn.pragma_update(None, "foreign_keys", true)?;
+        migrations::migrate(&mut conn)?;
+        journal::create_triggers(&conn)?;
+        Ok(SqliteStore { conn })
+    }
 
-    conn.execute(
-        &format!("UPDATE {TABLE_BOARDS} SET title = ?1 WHERE id = ?2"),
-        params![title, id],
-    )
-    .unwrap();
-    Ok(())
-}
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<SqliteStore> {
+        SqliteStore::new(Connection::open(path)?)
+    }
 
-pub fn get_comments_by_task_id(task_id: u16) -> Result<Vec<Comment>> {
-    let conn = get_connection();
-    let query = format!("SELECT * FROM {TABLE_COMMENTS} WHERE task_id = {task_id}");
-    let mut comments: Vec<Comment> = Vec::new();
+    pub fn open_in_memory() -> Result<SqliteStore> {
+        SqliteStore::new(Connection::open_in_memory()?)
+    }
 
-    let mut stmt = conn.prepare(&query)?;
+    pub fn connection(&self) -> &Connection {
+        &self.conn
+    }
 
-    let result_iter = stmt.query_map([], |row| {
-        Ok(Comment {
-            id: row.get(0)?,
-            title: row.get(1)?,
-            created_at: row.get(3)?,
+    ///Savepoints nest, unlike transactions, so store methods can run inside `transaction`
+    fn savepoint(&self) -> rusqlite::Result<Savepoint<'_>> {
+        self.conn.execute_batch("SAVEPOINT store")?;
+        Ok(Savepoint {
+            conn: &self.conn,
+            released: false,
         })
-    })?;
+    }
 
-    for i in result_iter {
-        comments.push(i?);
+    fn recording(&self) -> Result<bool> {
+        let recording = self.conn.query_row(
+            &format!("SELECT EXISTS (SELECT 1 FROM {TABLE_RECORDING})"),
+            [],
+            |row| row.get(0),
+        )?;
+        Ok(recording)
     }
-    Ok(comments)
-}
 
-///title, id
-pub fn select_task(done: u8) -> Option<(String, u16)> {
-    let records_qtd = get_records_qtd(TABLE_TASKS).unwrap();
-    if records_qtd == 0 {
-        display_message("info", "No Tasks found in database", Color::Cyan);
-        return None;
+    fn query_operation(&self, clauses: &str) -> Result<Option<Operation>> {
+        let operation = self
+            .conn
+            .query_row(
+                &format!("SELECT {OPERATION_COLUMNS} FROM {TABLE_OPERATIONS}{clauses}"),
+                [],
+                operation_from_row,
+            )
+            .optional()?;
+        Ok(operation)
     }
-    let mut query = format!("SELECT * FROM {TABLE_TASKS} WHERE done = {done}");
 
-    let all_boards = get_user_confirmation("From all boards");
+    ///Run the statements reverting the operation, latest first, while recording the statements
+    ///reverting them in turn. The operation then flips between applied and undone
+    fn replay(&self, operation: &mut Operation) -> Result<()> {
+        let tx = self.savepoint()?;
+        //Rows come back in reverse order, parents may be restored after their children
+        tx.pragma_update(None, "defer_foreign_keys", true)?;
+        let mut stmt = tx.prepare(&format!(
+            "SELECT statement FROM {TABLE_JOURNAL} WHERE operation_id = ?1 ORDER BY id DESC"
+        ))?;
+        let statements = stmt
+            .query_map(params![operation.id], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<String>>>()?;
+        drop(stmt);
+        tx.execute(
+            &format!("DELETE FROM {TABLE_JOURNAL} WHERE operation_id = ?1"),
+            params![operation.id],
+        )?;
 
-    if !all_boards {
-        let (_, board_id) = select_board().unwrap();
-        query.push_str(&format!(" AND board_id = {board_id}"));
+        tx.execute(
+            &format!("INSERT INTO {TABLE_RECORDING} (operation_id) VALUES (?1)"),
+            params![operation.id],
+        )?;
+        for statement in &statements {
+            tx.execute_batch(statement).map_err(|e| {
+                Error::Validation(format!(
+                    "Operation {} \"{}\" can't be replayed: {}",
+                    operation.id, operation.description, e
+                ))
+            })?;
+        }
+        tx.execute(&format!("DELETE FROM {TABLE_RECORDING}"), [])?;
+
+        operation.undone = !operation.undone;
+        tx.execute(
+            &format!("UPDATE {TABLE_OPERATIONS} SET undone = ?1 WHERE id = ?2"),
+            params![operation.undone, operation.id],
+        )?;
+        Ok(tx.commit()?)
     }
 
-    let tasks_raw = get_tasks(&query).unwrap();
-    if tasks_raw.len() == 0 {
-        display_message(
-            "info",
-            "No Tasks found in database with these criteria",
-            Color::Cyan,
+    ///Tasks with their subtask and blocker counts, tags and column title, followed by the clauses
+    fn query_tasks<P: rusqlite::Params>(&self, clauses: &str, params: P) -> Result<Vec<Task>> {
+        let query = format!(
+            "SELECT {TASK_COLUMNS},
+            (SELECT COUNT(*) FROM {TABLE_TASKS} AS subtasks WHERE subtasks.parent_id = {TABLE_TASKS}.id),
+            (SELECT COUNT(*) FROM {TABLE_TASKS} AS subtasks WHERE subtasks.parent_id = {TABLE_TASKS}.id AND subtasks.done = 1),
+            (SELECT COUNT(*) FROM {TABLE_DEPENDENCIES}
+                JOIN {TABLE_TASKS} AS blockers ON blockers.id = {TABLE_DEPENDENCIES}.blocker_id
+                WHERE {TABLE_DEPENDENCIES}.task_id = {TABLE_TASKS}.id AND blockers.done = 0),
+            (SELECT GROUP_CONCAT({TABLE_TAGS}.name) FROM {TABLE_TASK_TAGS}
+                JOIN {TABLE_TAGS} ON {TABLE_TAGS}.id = {TABLE_TASK_TAGS}.tag_id
+                WHERE {TABLE_TASK_TAGS}.task_id = {TABLE_TASKS}.id),
+            (SELECT title FROM {TABLE_COLUMNS} WHERE {TABLE_COLUMNS}.id = {TABLE_TASKS}.column_id)
+            FROM {TABLE_TASKS}{clauses}"
         );
-        return None;
-    }
+        let mut stmt = self.conn.prepare(&query)?;
+        let mut records: Vec<Task> = Vec::new();
 
-    let mut tasks: Vec<String> = tasks_raw
-        .iter()
-        .map(|x| format!("{} - {}", &x.id, &x.title))
-        .collect();
+        let result_iter = stmt.query_map(params, task_from_row)?;
 
-    tasks.sort();
+        for i in result_iter {
+            records.push(i?);
+        }
+        Ok(records)
+    }
 
-    let (selected_task, _) = get_user_selection(&tasks, "Task");
+    ///First terminal or regular column of the board, None when the board is missing
+    fn default_column(&self, board_id: u16, done: bool) -> Result<Option<u16>> {
+        let column_id = self
+            .conn
+            .query_row(
+                &format!("SELECT id FROM {TABLE_COLUMNS} WHERE board_id = ?1 AND terminal = ?2 ORDER BY position LIMIT 1"),
+                params![board_id, done],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(column_id)
+    }
 
-    let task_id: u16 = selected_task
-        .split_whitespace()
-        .next()
-        .unwrap()
-        .parse()
-        .unwrap();
+    fn get_reminder(&self, id: u16) -> Result<Reminder> {
+        let reminder = self
+            .conn
+            .query_row(
+                &format!("SELECT {REMINDER_COLUMNS} FROM {TABLE_REMINDERS} WHERE id = ?1"),
+                params![id],
+                reminder_from_row,
+            )
+            .optional()?;
+        reminder.ok_or_else(|| Error::NotFound(format!("Reminder {}", id)))
+    }
+
+    ///Append a change to the history of the task
+    fn record(
+        &self,
+        task_id: u16,
+        action: HistoryAction,
+        field: &str,
+        old_value: Option<String>,
+        new_value: Option<String>,
+    ) -> Result<()> {
+        self.conn.execute(
+            &format!("INSERT INTO {TABLE_HISTORY} (task_id, action, field, old_value, new_value, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)"),
+            params![task_id, action, field, old_value, new_value, Utc::now().trunc_subsecs(0)],
+        )?;
+        Ok(())
+    }
 
-    let selected_task = tasks_raw.iter().find(|x| x.id == task_id).unwrap();
-    let selected_task = (selected_task.title.to_string(), selected_task.id);
-    Some(selected_task)
+    ///Rewrite the positions of the columns of the board as 0, 1, 2... in this order
+    fn save_positions(&self, columns: &[Column]) -> Result<()> {
+        for (position, column) in columns.iter().enumerate() {
+            self.conn.execute(
+                &format!("UPDATE {TABLE_COLUMNS} SET position = ?1 WHERE id = ?2"),
+                params![position, column.id],
+            )?;
+        }
+        Ok(())
+    }
 }
 
-pub fn list_delayed_tasks() {
-    let query = format!("SELECT * FROM {TABLE_TASKS} WHERE done = 0");
-    let tasks_raw = get_tasks(&query).unwrap();
+///Rolled back when dropped without commit
+struct Savepoint<'a> {
+    conn: &'a Connection,
+    released: bool,
+}
 
-    if tasks_raw.len() == 0 {
-        return;
+impl Savepoint<'_> {
+    ///A failed release, e.g. on deferred foreign keys, still rolls back on drop
+    fn commit(mut self) -> rusqlite::Result<()> {
+        self.conn.execute_batch("RELEASE store")?;
+        self.released = true;
+        Ok(())
     }
+}
 
-    let delayed_tasks: Vec<&Task> = tasks_raw
-        .iter()
-        .filter(|x| datetime_str_is_past(&x.reminder))
-        .collect();
+impl Deref for Savepoint<'_> {
+    type Target = Connection;
 
-    if delayed_tasks.len() == 0 {
-        return;
+    fn deref(&self) -> &Connection {
+        self.conn
     }
-    println!("Delayed Tasks: {}", &delayed_tasks.len());
+}
 
-    let table = Table::new(delayed_tasks)
-        .with(Style::modern())
-        .with(Disable::Column(2..4));
+impl Drop for Savepoint<'_> {
+    fn drop(&mut self) {
+        if !self.released {
+            let _ = self.conn.execute_batch("ROLLBACK TO store; RELEASE store");
+        }
+    }
+}
 
-    println!("{}", table);
+///Id of the row just inserted. A rowid past the u16 ids fails, and rolls the insert back,
+///rather than wrapping onto the id of another row
+fn inserted_id(conn: &Connection, table: &str) -> Result<u16> {
+    u16::try_from(conn.last_insert_rowid())
+        .map_err(|_| Error::Validation(format!("No ids left in {}", table)))
 }
 
-//Delete database records
-pub fn delete_record_by_id(table: &str, id: u16) -> Result<()> {
-    let conn = get_connection();
-    conn.execute(&format!("DELETE FROM {table} WHERE id = ?1"), params![id])?;
-    Ok(())
+fn operation_from_row(row: &Row) -> rusqlite::Result<Operation> {
+    Ok(Operation {
+        id: row.get(0)?,
+        description: row.get(1)?,
+        created_at: row.get(2)?,
+        undone: row.get(3)?,
+    })
 }
 
-pub fn delete_tasks_by_board_id(board_id: &u16) -> Result<()> {
-    let conn = get_connection();
-    conn.execute(
-        &format!("DELETE FROM {TABLE_TASKS} WHERE board_id = ?1"),
-        params![board_id],
-    )?;
-    Ok(())
+fn task_from_row(row: &Row) -> rusqlite::Result<Task> {
+    Ok(Task {
+        id: row.get(0)?,
+        title: row.get(1)?,
+        done: row.get(2)?,
+        board_id: row.get(3)?,
+        created_at: row.get(4)?,
+        due_at: row.get(5)?,
+        priority: row.get(6)?,
+        recurrence: row.get(7)?,
+        parent_id: row.get(8)?,
+        column_id: row.get(9)?,
+        completed_at: row.get(10)?,
+        subtasks: Progress {
+            total: row.get(11)?,
+            done: row.get(12)?,
+        },
+        blockers: row.get(13)?,
+        tags: split_tags(row.get(14)?),
+        column: row.get::<_, Option<String>>(15)?.unwrap_or_default(),
+    })
 }
 
-pub fn delete_comments_by_task_id(task_id: &u16) -> Result<()> {
-    let conn = get_connection();
-    conn.execute(
-        &format!("DELETE FROM {TABLE_COMMENTS} WHERE task_id = ?1"),
-        params![task_id],
-    )?;
-    Ok(())
+fn column_from_row(row: &Row) -> rusqlite::Result<Column> {
+    Ok(Column {
+        id: row.get(0)?,
+        board_id: row.get(1)?,
+        title: row.get(2)?,
+        position: row.get(3)?,
+        terminal: row.get(4)?,
+        tasks: row.get(5)?,
+    })
 }
 
-pub fn get_boards() -> Result<Vec<Board>> {
-    let conn = get_connection();
-    let query = format!("SELECT * FROM {TABLE_BOARDS}");
-    let mut records: Vec<Board> = Vec::new();
+//Tag names can't contain commas, the GROUP_CONCAT separator
+fn split_tags(tags: Option<String>) -> Vec<String> {
+    let mut tags: Vec<String> = tags
+        .unwrap_or_default()
+        .split(',')
+        .filter(|x| !x.is_empty())
+        .map(String::from)
+        .collect();
+    tags.sort_by_key(|x| x.to_lowercase());
+    tags
+}
 
-    let mut stmt = conn.prepare(&query)?;
+fn history_from_row(row: &Row) -> rusqlite::Result<HistoryEntry> {
+    Ok(HistoryEntry {
+        id: row.get(0)?,
+        task_id: row.get(1)?,
+        created_at: row.get(2)?,
+        action: row.get(3)?,
+        field: row.get(4)?,
+        old_value: row.get(5)?,
+        new_value: row.get(6)?,
+    })
+}
 
-    let result_iter = stmt.query_map([], |row| {
-        Ok(Board {
-            id: row.get(0)?,
-            title: row.get(1)?,
-        })
-    })?;
+//Fields changed by an edit as (field, old value, new value), the column and status aside
+fn edited_fields(
+    previous: &Task,
+    task: &Task,
+) -> Vec<(&'static str, Option<String>, Option<String>)> {
+    let mut fields = vec![
+        (
+            FIELD_TITLE,
+            Some(previous.title.clone()),
+            Some(task.title.clone()),
+        ),
+        (
+            FIELD_PRIORITY,
+            Some(previous.priority.to_string()),
+            Some(task.priority.to_string()),
+        ),
+        (
+            FIELD_DUE,
+            previous.due_at.map(|x| x.to_string()),
+            task.due_at.map(|x| x.to_string()),
+        ),
+        (
+            FIELD_RECURRENCE,
+            previous.recurrence.as_ref().map(|x| x.to_string()),
+            task.recurrence.as_ref().map(|x| x.to_string()),
+        ),
+        (
+            FIELD_PARENT,
+            previous.parent_id.map(|x| x.to_string()),
+            task.parent_id.map(|x| x.to_string()),
+        ),
+        (
+            FIELD_BOARD,
+            Some(previous.board_id.to_string()),
+            Some(task.board_id.to_string()),
+        ),
+    ];
+    fields.retain(|x| x.1 != x.2);
+    fields
+}
 
-    for i in result_iter {
-        records.push(i?);
+fn describe_reminder(remind_at: DateTime<Utc>, before_due: Option<Duration>) -> String {
+    match before_due {
+        Some(before_due) if before_due.is_zero() => "at due".to_string(),
+        Some(before_due) => format!("{} before due", format_duration(before_due)),
+        None => remind_at.to_string(),
     }
-    Ok(records)
 }
 
-pub fn create_comment(task_id: u16) -> Result<()> {
-    let conn = get_connection();
+//before_due is stored in seconds
+fn reminder_from_row(row: &Row) -> rusqlite::Result<Reminder> {
+    Ok(Reminder {
+        id: row.get(0)?,
+        task_id: row.get(1)?,
+        remind_at: row.get(2)?,
+        before_due: row.get::<_, Option<i64>>(3)?.map(Duration::seconds),
+        fired_at: row.get(4)?,
+    })
+}
 
-    let title = get_user_input("Comment title", SAMPLE_TITLE, true).unwrap();
-    let created_at = Utc::now().to_rfc2822();
+impl TaskStore for SqliteStore {
+    fn count_records(&self, table: &str) -> Result<u16> {
+        let qtd = self
+            .conn
+            .query_row(&format!("SELECT COUNT(*) FROM {table}"), [], |row| {
+                row.get(0)
+            })?;
+        Ok(qtd)
+    }
 
-    conn.execute(
-        &format!("INSERT INTO {TABLE_COMMENTS} (title, task_id, created_at) VALUES (?1, ?2, ?3)"),
-        params![title, task_id, created_at],
-    )?;
-    Ok(())
-}
+    fn transaction(&self, commit: bool, f: &mut dyn FnMut() -> Result<()>) -> Result<()> {
+        let tx = self.savepoint()?;
+        f()?;
+        match commit {
+            true => Ok(tx.commit()?),
+            false => Ok(()),
+        }
+    }
 
-pub fn set_reminder(task_id: u16) -> Result<()> {
-    let conn = get_connection();
+    fn journaled(&self, description: &str, f: &mut dyn FnMut() -> Result<()>) -> Result<()> {
+        if self.recording()? {
+            return f();
+        }
+        //A failing `f` leaves neither its changes nor the operation behind
+        let tx = self.savepoint()?;
+        tx.execute(
+            &format!("INSERT INTO {TABLE_OPERATIONS} (description, created_at) VALUES (?1, ?2)"),
+            params![description, Utc::now().trunc_subsecs(0)],
+        )?;
+        let id = tx.last_insert_rowid();
+        tx.execute(
+            &format!("INSERT INTO {TABLE_RECORDING} (operation_id) VALUES (?1)"),
+            params![id],
+        )?;
+        let result = f();
+        tx.execute(&format!("DELETE FROM {TABLE_RECORDING}"), [])?;
+        result?;
 
-    let reminder = get_user_date(true, false);
-    if reminder.is_none() {
-        return Ok(());
+        let changed: bool = tx.query_row(
+            &format!("SELECT EXISTS (SELECT 1 FROM {TABLE_JOURNAL} WHERE operation_id = ?1)"),
+            params![id],
+            |row| row.get(0),
+        )?;
+        match changed {
+            true => tx.execute(
+                &format!("DELETE FROM {TABLE_OPERATIONS} WHERE undone = 1 OR id NOT IN (SELECT id FROM {TABLE_OPERATIONS} ORDER BY id DESC LIMIT ?1)"),
+                params![JOURNAL_LIMIT],
+            )?,
+            false => tx.execute(
+                &format!("DELETE FROM {TABLE_OPERATIONS} WHERE id = ?1"),
+                params![id],
+            )?,
+        };
+        Ok(tx.commit()?)
     }
 
-    conn.execute(
-        &format!("UPDATE {TABLE_TASKS} SET reminder = ?1 WHERE id = ?2"),
-        params![reminder, task_id],
-    )?;
-    Ok(())
-}
+    fn undo(&self) -> Result<Option<Operation>> {
+        let operation = self.query_operation(" WHERE undone = 0 ORDER BY id DESC LIMIT 1")?;
+        match operation {
+            Some(mut operation) => {
+                self.replay(&mut operation)?;
+                Ok(Some(operation))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn redo(&self) -> Result<Option<Operation>> {
+        let operation = self.query_operation(" WHERE undone = 1 ORDER BY id LIMIT 1")?;
+        match operation {
+            Some(mut operation) => {
+                self.replay(&mut operation)?;
+                Ok(Some(operation))
+            }
+            None => Ok(None),
+        }
+    }
 
-pub fn create_task() -> Result<()> {
-    let conn = get_connection();
-    let boards_qtd = get_records_qtd(TABLE_BOARDS).unwrap();
+    fn list_operations(&self, limit: u32) -> Result<Vec<Operation>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {OPERATION_COLUMNS} FROM {TABLE_OPERATIONS} ORDER BY id DESC LIMIT ?1"
+        ))?;
+        let operations = stmt
+            .query_map(params![limit], operation_from_row)?
+            .collect::<rusqlite::Result<Vec<Operation>>>()?;
+        Ok(operations)
+    }
 
-    if boards_qtd == 0 {
-        display_message("info", "Create a initial Board", Color::Cyan);
-        create_board().unwrap();
+    fn add_board(&self, title: &str) -> Result<u16> {
+        let tx = self.savepoint()?;
+        tx.execute(
+            &format!("INSERT INTO {TABLE_BOARDS} (title) VALUES (?1)"),
+            params![title],
+        )?;
+        let board_id = inserted_id(&tx, TABLE_BOARDS)?;
+        for (position, (title, terminal)) in DEFAULT_COLUMNS.iter().enumerate() {
+            tx.execute(
+                &format!("INSERT INTO {TABLE_COLUMNS} (board_id, title, position, terminal) VALUES (?1, ?2, ?3, ?4)"),
+                params![board_id, title, position, terminal],
+            )?;
+        }
+        tx.commit()?;
+        Ok(board_id)
     }
 
-    let title = get_user_input("Task title", SAMPLE_TITLE, true).unwrap();
-    let board_id = select_board().unwrap().1;
-    let created_at = Utc::now().format(DATETIME_FORMAT).to_string();
-    let with_reminder = get_user_confirmation("Set reminder");
+    fn get_board(&self, id: u16) -> Result<Board> {
+        self.list_boards()?
+            .into_iter()
+            .find(|x| x.id == id)
+            .ok_or_else(|| Error::NotFound(format!("Board {}", id)))
+    }
 
-    let reminder = match with_reminder {
-        true => {
-            let user_date = get_user_date(true, true);
-            if user_date.is_none() {
-                return Ok(());
+    fn list_boards(&self) -> Result<Vec<Board>> {
+        let query = format!("SELECT {BOARD_COLUMNS} FROM {TABLE_BOARDS}");
+        let mut records: Vec<Board> = Vec::new();
+
+        let mut stmt = self.conn.prepare(&query)?;
+
+        let result_iter = stmt.query_map([], |row| {
+            Ok(Board {
+                id: row.get(0)?,
+                title: row.get(1)?,
+            })
+        })?;
+
+        for i in result_iter {
+            records.push(i?);
+        }
+        Ok(records)
+    }
+
+    fn rename_board(&self, id: u16, title: &str) -> Result<()> {
+        self.conn.execute(
+            &format!("UPDATE {TABLE_BOARDS} SET title = ?1 WHERE id = ?2"),
+            params![title, id],
+        )?;
+        Ok(())
+    }
+
+    fn delete_board(&self, id: u16) -> Result<()> {
+        let tx = self.savepoint()?;
+        tx.execute(
+            &format!("INSERT INTO {TABLE_HISTORY} (task_id, action, old_value, created_at) SELECT id, ?1, title, ?2 FROM {TABLE_TASKS} WHERE board_id = ?3"),
+            params![HistoryAction::Deleted, Utc::now().trunc_subsecs(0), id],
+        )?;
+        tx.execute(
+            &format!("DELETE FROM {TABLE_BOARDS} WHERE id = ?1"),
+            params![id],
+        )?;
+        Ok(tx.commit()?)
+    }
+
+    fn add_column(&self, board_id: u16, title: &str) -> Result<u16> {
+        self.get_board(board_id)?;
+        let mut columns = self.list_columns(board_id)?;
+        let tx = self.savepoint()?;
+        tx.execute(
+            &format!("INSERT INTO {TABLE_COLUMNS} (board_id, title, position, terminal) VALUES (?1, ?2, ?3, 0)"),
+            params![board_id, title, columns.len()],
+        )?;
+        let id = inserted_id(&tx, TABLE_COLUMNS)?;
+        let terminal = columns
+            .iter()
+            .position(|x| x.terminal)
+            .unwrap_or(columns.len());
+        columns.insert(terminal, self.get_column(id)?);
+        self.save_positions(&columns)?;
+        tx.commit()?;
+        Ok(id)
+    }
+
+    fn get_column(&self, id: u16) -> Result<Column> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {COLUMN_COLUMNS}, (SELECT COUNT(*) FROM {TABLE_TASKS} WHERE column_id = {TABLE_COLUMNS}.id)
+            FROM {TABLE_COLUMNS} WHERE id = ?1"
+        ))?;
+        stmt.query_row(params![id], column_from_row)
+            .optional()?
+            .ok_or_else(|| Error::NotFound(format!("Column {}", id)))
+    }
+
+    fn list_columns(&self, board_id: u16) -> Result<Vec<Column>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {COLUMN_COLUMNS}, (SELECT COUNT(*) FROM {TABLE_TASKS} WHERE column_id = {TABLE_COLUMNS}.id)
+            FROM {TABLE_COLUMNS} WHERE board_id = ?1 ORDER BY position, id"
+        ))?;
+        let columns = stmt
+            .query_map(params![board_id], column_from_row)?
+            .collect::<rusqlite::Result<Vec<Column>>>()?;
+        Ok(columns)
+    }
+
+    fn rename_column(&self, id: u16, title: &str) -> Result<()> {
+        self.get_column(id)?;
+        self.conn.execute(
+            &format!("UPDATE {TABLE_COLUMNS} SET title = ?1 WHERE id = ?2"),
+            params![title, id],
+        )?;
+        Ok(())
+    }
+
+    fn move_column(&self, id: u16, position: u16) -> Result<()> {
+        let column = self.get_column(id)?;
+        let mut columns: Vec<Column> = self
+            .list_columns(column.board_id)?
+            .into_iter()
+            .filter(|x| x.id != id)
+            .collect();
+        let position = (position as usize).min(columns.len());
+        columns.insert(position, column);
+        let tx = self.savepoint()?;
+        self.save_positions(&columns)?;
+        Ok(tx.commit()?)
+    }
+
+    fn set_terminal_column(&self, id: u16) -> Result<()> {
+        let column = self.get_column(id)?;
+        let tx = self.savepoint()?;
+        tx.execute(
+            &format!("UPDATE {TABLE_COLUMNS} SET terminal = (id = ?1) WHERE board_id = ?2"),
+            params![id, column.board_id],
+        )?;
+        //Tasks whose done flag changes, with their new flag
+        let terminal = format!("(SELECT terminal FROM {TABLE_COLUMNS} WHERE {TABLE_COLUMNS}.id = {TABLE_TASKS}.column_id)");
+        let now = Utc::now().trunc_subsecs(0);
+        tx.execute(
+            &format!("INSERT INTO {TABLE_HISTORY} (task_id, action, field, old_value, new_value, created_at)
+            SELECT id, ?1, ?2, CASE done WHEN 1 THEN ?3 ELSE ?4 END, CASE done WHEN 1 THEN ?4 ELSE ?3 END, ?5
+            FROM {TABLE_TASKS} WHERE board_id = ?6 AND done != {terminal}"),
+            params![HistoryAction::Status, FIELD_STATUS, status_name(true), status_name(false), now, column.board_id],
+        )?;
+        tx.execute(
+            &format!("UPDATE {TABLE_TASKS} SET completed_at = CASE WHEN {terminal} = 0 THEN NULL WHEN done = 0 THEN ?1 ELSE completed_at END,
+            done = {terminal} WHERE board_id = ?2"),
+            params![now, column.board_id],
+        )?;
+        Ok(tx.commit()?)
+    }
+
+    fn delete_column(&self, id: u16) -> Result<()> {
+        let column = self.get_column(id)?;
+        if column.terminal {
+            return Err(Error::Validation(format!(
+                "Column {} is the terminal column of its board",
+                column.title
+            )));
+        }
+        let first = self
+            .list_columns(column.board_id)?
+            .into_iter()
+            .find(|x| x.id != id && !x.terminal)
+            .ok_or_else(|| {
+                Error::Validation(format!(
+                    "Column {} is the last one of its board",
+                    column.title
+                ))
+            })?;
+
+        let tx = self.savepoint()?;
+        tx.execute(
+            &format!("UPDATE {TABLE_TASKS} SET column_id = ?1 WHERE column_id = ?2"),
+            params![first.id, id],
+        )?;
+        tx.execute(
+            &format!("DELETE FROM {TABLE_COLUMNS} WHERE id = ?1"),
+            params![id],
+        )?;
+        let columns = self.list_columns(column.board_id)?;
+        self.save_positions(&columns)?;
+        Ok(tx.commit()?)
+    }
+
+    fn add_task(&self, task: &NewTask) -> Result<u16> {
+        let created_at = task.created_at.unwrap_or_else(Utc::now).trunc_subsecs(0);
+        if let Some(parent_id) = task.parent_id {
+            self.get_task(parent_id)?;
+        }
+        let column = match task.column_id {
+            Some(column_id) => self.get_column(column_id)?,
+            None => {
+                let column_id = self
+                    .default_column(task.board_id, false)?
+                    .ok_or_else(|| Error::NotFound(format!("Board {}", task.board_id)))?;
+                self.get_column(column_id)?
             }
-            user_date.unwrap()
+        };
+        if column.board_id != task.board_id {
+            return Err(Error::Validation(format!(
+                "Column {} is not on board {}",
+                column.title, task.board_id
+            )));
         }
-        false => "".to_string(),
-    };
-
-    conn.execute(
-        &format!("INSERT INTO {TABLE_TASKS} (title, done, board_id, created_at, reminder) VALUES (?1, ?2, ?3, ?4, ?5)"),
-        params![title, 0, board_id, created_at, reminder],
-    )
-    .unwrap();
-    Ok(())
-}
 
-pub fn create_board() -> Result<()> {
-    let conn = get_connection();
-    let title = get_user_input("Board title", SAMPLE_TITLE, true).unwrap();
-    let board = Board { id: 0, title };
-
-    conn.execute(
-        &format!("INSERT INTO {TABLE_BOARDS} (title) VALUES (?1)"),
-        params![board.title],
-    )
-    .unwrap();
-    Ok(())
-}
+        let completed_at = match column.terminal {
+            true => Some(task.completed_at.unwrap_or_else(Utc::now).trunc_subsecs(0)),
+            false => None,
+        };
 
-pub fn switch_task_status(task_id: u16) -> Result<()> {
-    let conn = get_connection();
+        let tx = self.savepoint()?;
+        tx.execute(
+            &format!("INSERT INTO {TABLE_TASKS} (title, done, board_id, created_at, due_at, priority, recurrence, parent_id, column_id, completed_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)"),
+            params![task.title, column.terminal, task.board_id, created_at, task.due_at, task.priority, task.recurrence, task.parent_id, column.id, completed_at],
+        )?;
+        let id = inserted_id(&tx, TABLE_TASKS)?;
+        self.record(
+            id,
+            HistoryAction::Created,
+            "",
+            None,
+            Some(task.title.clone()),
+        )?;
+        tx.commit()?;
+        Ok(id)
+    }
 
-    let done: u8 = match get_user_confirmation("Done") {
-        true => 1,
-        false => 0,
-    };
+    fn get_task(&self, id: u16) -> Result<Task> {
+        self.query_tasks(&format!(" WHERE {TABLE_TASKS}.id = ?1"), params![id])?
+            .into_iter()
+            .next()
+            .ok_or_else(|| Error::NotFound(format!("Task {}", id)))
+    }
+
+    fn list_tasks(&self, filter: &TaskFilter) -> Result<Vec<Task>> {
+        let (clauses, params) = filter.to_sql();
+        self.query_tasks(&clauses, params_from_iter(params))
+    }
 
-    conn.execute(
-        &format!("UPDATE {TABLE_TASKS} SET done = ?1 WHERE id = ?2"),
-        params![done, task_id],
-    )
-    .unwrap();
-    Ok(())
+    fn update_task(&self, task: &Task) -> Result<()> {
+        //Walk up from the new parent, meeting the task means a cycle
+        let mut ancestor = task.parent_id;
+        while let Some(id) = ancestor {
+            if id == task.id {
+                return Err(Error::Validation(format!(
+                    "Task {} can't be a subtask of itself or of its subtasks",
+                    task.id
+                )));
+            }
+            ancestor = self.get_task(id)?.parent_id;
+        }
+
+        //An explicit column change wins over the done flag
+        let previous = self.get_task(task.id)?;
+        let column = match task.column_id {
+            Some(column_id) if task.column_id != previous.column_id => {
+                Some(self.get_column(column_id)?)
+            }
+            Some(column_id) if task.board_id == previous.board_id && task.done == previous.done => {
+                Some(self.get_column(column_id)?)
+            }
+            _ => match self.default_column(task.board_id, task.done == 1)? {
+                Some(column_id) => Some(self.get_column(column_id)?),
+                None => None,
+            },
+        };
+        if let Some(column) = &column {
+            if column.board_id != task.board_id {
+                return Err(Error::Validation(format!(
+                    "Column {} is not on board {}",
+                    column.title, task.board_id
+                )));
+            }
+        }
+        let done = match &column {
+            Some(column) => column.terminal as u8,
+            None => task.done,
+        };
+        let completed_at = match (previous.done, done) {
+            (0, 1) => Some(Utc::now().trunc_subsecs(0)),
+            (_, 1) => previous.completed_at,
+            _ => None,
+        };
+
+        let tx = self.savepoint()?;
+        tx.execute(
+            &format!("UPDATE {TABLE_TASKS} SET title = ?1, done = ?2, board_id = ?3, due_at = ?4, priority = ?5, recurrence = ?6, parent_id = ?7, column_id = ?8, completed_at = ?9 WHERE id = ?10"),
+            params![task.title, done, task.board_id, task.due_at, task.priority, task.recurrence, task.parent_id, column.as_ref().map(|x| x.id), completed_at, task.id],
+        )?;
+        for (field, old_value, new_value) in edited_fields(&previous, task) {
+            self.record(task.id, HistoryAction::Edited, field, old_value, new_value)?;
+        }
+        let column_title = column.map(|x| x.title);
+        if column_title.is_some() && column_title.as_ref() != Some(&previous.column) {
+            self.record(
+                task.id,
+                HistoryAction::Edited,
+                FIELD_COLUMN,
+                Some(previous.column.clone()),
+                column_title,
+            )?;
+        }
+        if done != previous.done {
+            self.record(
+                task.id,
+                HistoryAction::Status,
+                FIELD_STATUS,
+                Some(status_name(previous.done == 1).to_string()),
+                Some(status_name(done == 1).to_string()),
+            )?;
+        }
+
+        let due_at = match task.due_at {
+            Some(due_at) => due_at,
+            None => {
+                tx.execute(
+                    &format!("DELETE FROM {TABLE_REMINDERS} WHERE task_id = ?1 AND before_due IS NOT NULL"),
+                    params![task.id],
+                )?;
+                return Ok(tx.commit()?);
+            }
+        };
+        //A moved reminder fires again
+        for reminder in self.list_reminders(task.id)? {
+            let remind_at = match reminder.before_due {
+                Some(before_due) => due_at - before_due,
+                None => continue,
+            };
+            if remind_at != reminder.remind_at {
+                tx.execute(
+                    &format!("UPDATE {TABLE_REMINDERS} SET remind_at = ?1, fired_at = NULL WHERE id = ?2"),
+                    params![remind_at, reminder.id],
+                )?;
+            }
+        }
+        Ok(tx.commit()?)
+    }
+
+    fn move_tasks(&self, filter: &TaskFilter, board_id: u16) -> Result<usize> {
+        let tx = self.savepoint()?;
+        for task in self.list_tasks(filter)? {
+            if task.board_id != board_id {
+                self.record(
+                    task.id,
+                    HistoryAction::Edited,
+                    FIELD_BOARD,
+                    Some(task.board_id.to_string()),
+                    Some(board_id.to_string()),
+                )?;
+            }
+        }
+        let (clauses, mut params) = filter.to_sql();
+        params.push(Box::new(board_id));
+        let query = format!(
+            "UPDATE {TABLE_TASKS} SET board_id = ?{0},
+            column_id = (SELECT id FROM {TABLE_COLUMNS} WHERE board_id = ?{0} AND terminal = {TABLE_TASKS}.done ORDER BY position LIMIT 1)
+            WHERE id IN (SELECT id FROM {TABLE_TASKS}{clauses})",
+            params.len()
+        );
+        let moved = tx.execute(&query, params_from_iter(params))?;
+        tx.commit()?;
+        Ok(moved)
+    }
+
+    fn delete_task(&self, id: u16) -> Result<()> {
+        let tx = self.savepoint()?;
+        tx.execute(
+            &format!("WITH RECURSIVE deleted(id) AS (
+                SELECT ?1 UNION SELECT {TABLE_TASKS}.id FROM {TABLE_TASKS} JOIN deleted ON {TABLE_TASKS}.parent_id = deleted.id
+            )
+            INSERT INTO {TABLE_HISTORY} (task_id, action, old_value, created_at)
+            SELECT id, ?2, title, ?3 FROM {TABLE_TASKS} WHERE id IN deleted"),
+            params![id, HistoryAction::Deleted, Utc::now().trunc_subsecs(0)],
+        )?;
+        tx.execute(
+            &format!("DELETE FROM {TABLE_TASKS} WHERE id = ?1"),
+            params![id],
+        )?;
+        Ok(tx.commit()?)
+    }
+
+    fn list_history(&self, task_id: u16) -> Result<Vec<HistoryEntry>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {HISTORY_COLUMNS} FROM {TABLE_HISTORY} WHERE task_id = ?1 ORDER BY id"
+        ))?;
+        let history = stmt
+            .query_map(params![task_id], history_from_row)?
+            .collect::<rusqlite::Result<Vec<HistoryEntry>>>()?;
+        Ok(history)
+    }
+
+    fn add_comment(&self, task_id: u16, title: &str) -> Result<u16> {
+        let created_at = Utc::now().trunc_subsecs(0);
+
+        let tx = self.savepoint()?;
+        tx.execute(
+            &format!(
+                "INSERT INTO {TABLE_COMMENTS} (title, task_id, created_at) VALUES (?1, ?2, ?3)"
+            ),
+            params![title, task_id, created_at],
+        )?;
+        let id = inserted_id(&tx, TABLE_COMMENTS)?;
+        tx.commit()?;
+        Ok(id)
+    }
+
+    fn list_comments(&self, task_id: u16) -> Result<Vec<Comment>> {
+        let query = format!("SELECT {COMMENT_COLUMNS} FROM {TABLE_COMMENTS} WHERE task_id = ?1");
+        let mut comments: Vec<Comment> = Vec::new();
+
+        let mut stmt = self.conn.prepare(&query)?;
+
+        let result_iter = stmt.query_map(params![task_id], |row| {
+            Ok(Comment {
+                id: row.get(0)?,
+                title: row.get(1)?,
+                created_at: row.get(3)?,
+            })
+        })?;
+
+        for i in result_iter {
+            comments.push(i?);
+        }
+        Ok(comments)
+    }
+
+    fn set_task_tags(&self, task_id: u16, tags: &[String]) -> Result<()> {
+        let previous = self.get_task(task_id)?.tags;
+        let tx = self.savepoint()?;
+        tx.execute(
+            &format!("DELETE FROM {TABLE_TASK_TAGS} WHERE task_id = ?1"),
+            params![task_id],
+        )?;
+        for name in tags {
+            tx.execute(
+                &format!("INSERT OR IGNORE INTO {TABLE_TAGS} (name) VALUES (?1)"),
+                params![name],
+            )?;
+            tx.execute(
+                &format!("INSERT OR IGNORE INTO {TABLE_TASK_TAGS} (task_id, tag_id) SELECT ?1, id FROM {TABLE_TAGS} WHERE name = ?2"),
+                params![task_id, name],
+            )?;
+        }
+        let tags = self.get_task(task_id)?.tags;
+        if tags != previous {
+            self.record(
+                task_id,
+                HistoryAction::Edited,
+                FIELD_TAGS,
+                Some(previous.join(", ")),
+                Some(tags.join(", ")),
+            )?;
+        }
+        Ok(tx.commit()?)
+    }
+
+    fn list_tags(&self) -> Result<Vec<Tag>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {TABLE_TAGS}.id, {TABLE_TAGS}.name, COUNT({TABLE_TASK_TAGS}.task_id) FROM {TABLE_TAGS}
+            LEFT JOIN {TABLE_TASK_TAGS} ON {TABLE_TASK_TAGS}.tag_id = {TABLE_TAGS}.id
+            GROUP BY {TABLE_TAGS}.id ORDER BY {TABLE_TAGS}.name"
+        ))?;
+        let tags = stmt
+            .query_map([], |row| {
+                Ok(Tag {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                    tasks: row.get(2)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<Tag>>>()?;
+        Ok(tags)
+    }
+
+    fn get_tag(&self, name: &str) -> Result<Tag> {
+        self.list_tags()?
+            .into_iter()
+            .find(|x| x.name.eq_ignore_ascii_case(name))
+            .ok_or_else(|| Error::NotFound(format!("Tag {}", name)))
+    }
+
+    fn rename_tag(&self, id: u16, name: &str) -> Result<()> {
+        if let Ok(existing) = self.get_tag(name) {
+            if existing.id != id {
+                return Err(Error::Validation(format!(
+                    "Tag {} already exists, merge them instead",
+                    existing.name
+                )));
+            }
+        }
+        self.conn.execute(
+            &format!("UPDATE {TABLE_TAGS} SET name = ?1 WHERE id = ?2"),
+            params![name, id],
+        )?;
+        Ok(())
+    }
+
+    fn merge_tags(&self, from_id: u16, into_id: u16) -> Result<()> {
+        if from_id == into_id {
+            return Err(Error::Validation(
+                "Can't merge a tag into itself".to_string(),
+            ));
+        }
+        let tx = self.savepoint()?;
+        tx.execute(
+            &format!("INSERT OR IGNORE INTO {TABLE_TASK_TAGS} (task_id, tag_id) SELECT task_id, ?2 FROM {TABLE_TASK_TAGS} WHERE tag_id = ?1"),
+            params![from_id, into_id],
+        )?;
+        tx.execute(
+            &format!("DELETE FROM {TABLE_TAGS} WHERE id = ?1"),
+            params![from_id],
+        )?;
+        Ok(tx.commit()?)
+    }
+
+    fn delete_tag(&self, id: u16) -> Result<()> {
+        self.conn.execute(
+            &format!("DELETE FROM {TABLE_TAGS} WHERE id = ?1"),
+            params![id],
+        )?;
+        Ok(())
+    }
+
+    fn add_dependency(&self, task_id: u16, blocker_id: u16) -> Result<()> {
+        self.get_task(task_id)?;
+        self.get_task(blocker_id)?;
+        //The blocker can't itself wait, directly or not, on the task
+        let cycle: bool = self.conn.query_row(
+            &format!(
+                "WITH RECURSIVE chain(id) AS (
+                    SELECT ?1
+                    UNION
+                    SELECT {TABLE_DEPENDENCIES}.blocker_id FROM {TABLE_DEPENDENCIES}
+                    JOIN chain ON {TABLE_DEPENDENCIES}.task_id = chain.id
+                )
+                SELECT EXISTS (SELECT 1 FROM chain WHERE id = ?2)"
+            ),
+            params![blocker_id, task_id],
+            |row| row.get(0),
+        )?;
+        if cycle {
+            return Err(Error::Validation(format!(
+                "Task {} already depends on task {}",
+                blocker_id, task_id
+            )));
+        }
+
+        self.conn.execute(
+            &format!(
+                "INSERT OR IGNORE INTO {TABLE_DEPENDENCIES} (task_id, blocker_id) VALUES (?1, ?2)"
+            ),
+            params![task_id, blocker_id],
+        )?;
+        Ok(())
+    }
+
+    fn remove_dependency(&self, task_id: u16, blocker_id: u16) -> Result<()> {
+        let deleted = self.conn.execute(
+            &format!("DELETE FROM {TABLE_DEPENDENCIES} WHERE task_id = ?1 AND blocker_id = ?2"),
+            params![task_id, blocker_id],
+        )?;
+        match deleted {
+            0 => Err(Error::NotFound(format!(
+                "Dependency of task {} on task {}",
+                task_id, blocker_id
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    fn list_blockers(&self, task_id: u16) -> Result<Vec<Task>> {
+        self.query_tasks(
+            &format!(
+                " WHERE {TABLE_TASKS}.id IN (SELECT blocker_id FROM {TABLE_DEPENDENCIES} WHERE task_id = ?1) ORDER BY {TABLE_TASKS}.id"
+            ),
+            params![task_id],
+        )
+    }
+
+    fn add_reminder(&self, task_id: u16, time: ReminderTime) -> Result<u16> {
+        let task = self.get_task(task_id)?;
+        let (remind_at, before_due) = match (time, task.due_at) {
+            (ReminderTime::At(remind_at), _) => (remind_at, None),
+            (ReminderTime::BeforeDue(before_due), Some(due_at)) => {
+                (due_at - before_due, Some(before_due.num_seconds()))
+            }
+            (ReminderTime::BeforeDue(_), None) => {
+                return Err(Error::Validation(format!(
+                    "Task {} has no due date",
+                    task_id
+                )))
+            }
+        };
+
+        let tx = self.savepoint()?;
+        tx.execute(
+            &format!(
+                "INSERT INTO {TABLE_REMINDERS} (task_id, remind_at, before_due) VALUES (?1, ?2, ?3)"
+            ),
+            params![task_id, remind_at, before_due],
+        )?;
+        let id = inserted_id(&tx, TABLE_REMINDERS)?;
+        self.record(
+            task_id,
+            HistoryAction::Reminder,
+            FIELD_REMINDER,
+            None,
+            Some(describe_reminder(
+                remind_at,
+                before_due.map(Duration::seconds),
+            )),
+        )?;
+        tx.commit()?;
+        Ok(id)
+    }
+
+    fn list_reminders(&self, task_id: u16) -> Result<Vec<Reminder>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {REMINDER_COLUMNS} FROM {TABLE_REMINDERS} WHERE task_id = ?1 ORDER BY remind_at, id"
+        ))?;
+        let reminders = stmt
+            .query_map(params![task_id], reminder_from_row)?
+            .collect::<rusqlite::Result<Vec<Reminder>>>()?;
+        Ok(reminders)
+    }
+
+    fn list_due_reminders(&self, now: DateTime<Utc>) -> Result<Vec<Reminder>> {
+        let columns: Vec<String> = REMINDER_COLUMNS
+            .split(", ")
+            .map(|x| format!("{TABLE_REMINDERS}.{x}"))
+            .collect();
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM {TABLE_REMINDERS}
+            JOIN {TABLE_TASKS} ON {TABLE_TASKS}.id = {TABLE_REMINDERS}.task_id
+            WHERE {TABLE_TASKS}.done = 0 AND {TABLE_REMINDERS}.fired_at IS NULL
+            AND {TABLE_REMINDERS}.remind_at <= ?1
+            ORDER BY {TABLE_REMINDERS}.remind_at, {TABLE_REMINDERS}.id",
+            columns.join(", ")
+        ))?;
+        let reminders = stmt
+            .query_map(params![now], reminder_from_row)?
+            .collect::<rusqlite::Result<Vec<Reminder>>>()?;
+        Ok(reminders)
+    }
+
+    fn update_reminder(&self, reminder: &Reminder) -> Result<()> {
+        let previous = self.get_reminder(reminder.id)?;
+        let tx = self.savepoint()?;
+        tx.execute(
+            &format!("UPDATE {TABLE_REMINDERS} SET remind_at = ?1, fired_at = ?2 WHERE id = ?3"),
+            params![reminder.remind_at, reminder.fired_at, reminder.id],
+        )?;
+        //Firing is not a change of the task
+        if reminder.remind_at != previous.remind_at {
+            self.record(
+                previous.task_id,
+                HistoryAction::Reminder,
+                FIELD_REMINDER,
+                Some(describe_reminder(previous.remind_at, previous.before_due)),
+                Some(describe_reminder(reminder.remind_at, None)),
+            )?;
+        }
+        Ok(tx.commit()?)
+    }
+
+    fn delete_reminder(&self, id: u16) -> Result<()> {
+        let reminder = self.get_reminder(id)?;
+        let tx = self.savepoint()?;
+        tx.execute(
+            &format!("DELETE FROM {TABLE_REMINDERS} WHERE id = ?1"),
+            params![id],
+        )?;
+        self.record(
+            reminder.task_id,
+            HistoryAction::Reminder,
+            FIELD_REMINDER,
+            Some(describe_reminder(reminder.remind_at, reminder.before_due)),
+            None,
+        )?;
+        Ok(tx.commit()?)
+    }
+
+    fn search(&self, text: &str, limit: u32) -> Result<Vec<SearchResult>> {
+        let query = match to_fts_query(text) {
+            Some(query) => query,
+            None => return Ok(Vec::new()),
+        };
+
+        let mut stmt = self.conn.prepare(&format!(
+            "WITH hits AS (
+                SELECT rowid AS task_id, bm25({TABLE_TASKS_SEARCH}) * {TITLE_MATCH_WEIGHT} AS rank
+                FROM {TABLE_TASKS_SEARCH} WHERE {TABLE_TASKS_SEARCH} MATCH ?1
+                UNION ALL
+                SELECT {TABLE_COMMENTS}.task_id, bm25({TABLE_COMMENTS_SEARCH})
+                FROM {TABLE_COMMENTS_SEARCH}
+                JOIN {TABLE_COMMENTS} ON {TABLE_COMMENTS}.id = {TABLE_COMMENTS_SEARCH}.rowid
+                WHERE {TABLE_COMMENTS_SEARCH} MATCH ?1
+            )
+            SELECT task_id, SUM(rank) AS score FROM hits GROUP BY task_id ORDER BY score LIMIT ?2"
+        ))?;
+        let hits = stmt
+            .query_map(params![query, limit], |row| {
+                Ok((row.get::<_, u16>(0)?, row.get::<_, f64>(1)?))
+            })?
+            .collect::<rusqlite::Result<Vec<(u16, f64)>>>()?;
+
+        let mut results: Vec<SearchResult> = Vec::new();
+        for (task_id, rank) in hits {
+            let task = self.get_task(task_id)?;
+            let title = self
+                .conn
+                .query_row(
+                    &format!(
+                        "SELECT highlight({TABLE_TASKS_SEARCH}, 0, ?1, ?2) FROM {TABLE_TASKS_SEARCH}
+                        WHERE {TABLE_TASKS_SEARCH} MATCH ?3 AND rowid = ?4"
+                    ),
+                    params![HIGHLIGHT_START, HIGHLIGHT_END, query, task_id],
+                    |row| row.get(0),
+                )
+                .optional()?
+                .unwrap_or_else(|| task.title.clone());
+
+            let mut stmt = self.conn.prepare(&format!(
+                "SELECT snippet({TABLE_COMMENTS_SEARCH}, 0, ?1, ?2, ?3, ?4) FROM {TABLE_COMMENTS_SEARCH}
+                JOIN {TABLE_COMMENTS} ON {TABLE_COMMENTS}.id = {TABLE_COMMENTS_SEARCH}.rowid
+                WHERE {TABLE_COMMENTS_SEARCH} MATCH ?5 AND {TABLE_COMMENTS}.task_id = ?6
+                ORDER BY bm25({TABLE_COMMENTS_SEARCH})"
+            ))?;
+            let comments = stmt
+                .query_map(
+                    params![
+                        HIGHLIGHT_START,
+                        HIGHLIGHT_END,
+                        SNIPPET_ELLIPSIS,
+                        SNIPPET_TOKENS,
+                        query,
+                        task_id
+                    ],
+                    |row| row.get(0),
+                )?
+                .collect::<rusqlite::Result<Vec<String>>>()?;
+
+            results.push(SearchResult {
+                task,
+                rank,
+                title,
+                comments,
+            });
+        }
+        Ok(results)
+    }
+
+    fn check_integrity(&self) -> Result<Vec<Orphan>> {
+        let mut stmt = self.conn.prepare("PRAGMA foreign_key_check")?;
+        let mut orphans: Vec<Orphan> = Vec::new();
+
+        let result_iter = stmt.query_map([], |row| {
+            Ok(Orphan {
+                table: row.get(0)?,
+                id: row.get(1)?,
+                parent: row.get(2)?,
+            })
+        })?;
+
+        for i in result_iter {
+            orphans.push(i?);
+        }
+        Ok(orphans)
+    }
+
+    fn repair_orphans(&self) -> Result<usize> {
+        let orphans = self.check_integrity()?;
+        let tx = self.savepoint()?;
+
+        for orphan in &orphans {
+            tx.execute(
+                &format!("DELETE FROM {} WHERE rowid = ?1", orphan.table),
+                params![orphan.id],
+            )?;
+        }
+        tx.commit()?;
+        Ok(orphans.len())
+    }
 }
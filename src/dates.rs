@@ -0,0 +1,371 @@
+use crate::{
+    error::{Error, Result},
+    DATETIME_FORMAT, DATE_FORMAT,
+};
+use chrono::{
+    DateTime, Datelike, Duration, Local, Months, NaiveDate, NaiveDateTime, NaiveTime, SubsecRound,
+    TimeZone, Utc, Weekday,
+};
+
+///Shown to the user when asking for a date
+pub const DATE_EXAMPLES: &str = "tomorrow 9am, in 3 days, next friday, +2h, 20261101 14:30";
+
+const DATE_FORMATS: [&str; 2] = [DATE_FORMAT, "%Y-%m-%d"];
+//Input is lowercased before parsing
+const ISO_DATETIME_FORMATS: [&str; 2] = ["%Y-%m-%dt%H:%M:%S", "%Y-%m-%dt%H:%M"];
+
+///Format a datetime for display, in the local timezone
+pub fn format_datetime(datetime: &DateTime<Utc>) -> String {
+    datetime
+        .with_timezone(&Local)
+        .format(DATETIME_FORMAT)
+        .to_string()
+}
+
+///"45m", "3h", "2d", rounded down to the largest unit
+pub fn format_duration(duration: Duration) -> String {
+    match duration.num_seconds().abs() {
+        s if s < 60 => format!("{}s", s),
+        s if s < 3600 => format!("{}m", s / 60),
+        s if s < 86400 => format!("{}h", s / 3600),
+        s if s < 86400 * 14 => format!("{}d", s / 86400),
+        s => format!("{}w", s / (86400 * 7)),
+    }
+}
+
+///"in 3h", "2d ago"
+pub fn format_relative(datetime: &DateTime<Utc>, now: &DateTime<Utc>) -> String {
+    let delta = *datetime - *now;
+    match delta.num_seconds() {
+        s if s.abs() < 60 => "now".to_string(),
+        s if s > 0 => format!("in {}", format_duration(delta)),
+        _ => format!("{} ago", format_duration(delta)),
+    }
+}
+
+///Shown to the user when asking for a reminder
+pub const REMINDER_EXAMPLES: &str = "1h before, 2 days before, at due, tomorrow 9am";
+
+///When a reminder fires, at a date or some time before the due date of its task
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReminderTime {
+    At(DateTime<Utc>),
+    BeforeDue(Duration),
+}
+
+///"1h before", "2 days before due", "at due" or any date parse_datetime accepts
+pub fn parse_reminder_time(text: &str) -> Result<ReminderTime> {
+    let lower = text.trim().to_lowercase();
+    if lower == "at due" {
+        return Ok(ReminderTime::BeforeDue(Duration::zero()));
+    }
+    let offset = lower
+        .strip_suffix(" before due")
+        .or_else(|| lower.strip_suffix(" before"));
+    match offset {
+        Some(offset) => parse_duration(offset)
+            .map(ReminderTime::BeforeDue)
+            .ok_or_else(|| Error::Validation(format!("Invalid duration {}", offset))),
+        None => parse_datetime(text).map(ReminderTime::At),
+    }
+}
+
+///Parse an absolute, relative or natural date in the local timezone
+pub fn parse_datetime(text: &str) -> Result<DateTime<Utc>> {
+    parse_datetime_from(text, Local::now())
+        .map(|x| x.with_timezone(&Utc).trunc_subsecs(0))
+        .ok_or_else(|| Error::Validation(format!("Invalid date {} (try {})", text, DATE_EXAMPLES)))
+}
+
+///Parse a date relative to `now`, in its timezone. Dates without a time are at midnight
+pub fn parse_datetime_from<Tz: TimeZone>(text: &str, now: DateTime<Tz>) -> Option<DateTime<Tz>> {
+    let text = text.trim().to_lowercase();
+    for format in ISO_DATETIME_FORMATS {
+        if let Ok(datetime) = NaiveDateTime::parse_from_str(&text, format) {
+            return now.timezone().from_local_datetime(&datetime).earliest();
+        }
+    }
+
+    let words: Vec<&str> = text.split_whitespace().collect();
+    match words.as_slice() {
+        [] => None,
+        ["now"] => Some(now),
+        ["in", rest @ ..] => add_duration(now, rest),
+        [word] if word.starts_with('+') => add_duration(now, &[&word[1..]]),
+        _ => parse_date_and_time(now, &words),
+    }
+}
+
+///Length of a duration word, months vary so they are kept apart
+enum Span {
+    Fixed(Duration),
+    Months(u32),
+}
+
+///"3 days", "1 hour 30 minutes", "2h30m", "an hour"
+fn parse_spans(words: &[&str]) -> Option<Vec<Span>> {
+    let text: String = words
+        .iter()
+        .filter(|x| **x != "and")
+        .map(|x| match *x {
+            "a" | "an" => "1",
+            x => x,
+        })
+        .collect();
+    if text.is_empty() {
+        return None;
+    }
+
+    let mut spans = Vec::new();
+    let mut rest = text.as_str();
+    while !rest.is_empty() {
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let amount: u32 = rest[..digits].parse().ok()?;
+        rest = &rest[digits..];
+        let letters = rest
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(rest.len());
+        let unit = &rest[..letters];
+        rest = &rest[letters..];
+
+        let amount_i64 = i64::from(amount);
+        spans.push(match unit {
+            "m" | "min" | "mins" | "minute" | "minutes" => {
+                Span::Fixed(Duration::minutes(amount_i64))
+            }
+            "h" | "hr" | "hrs" | "hour" | "hours" => Span::Fixed(Duration::hours(amount_i64)),
+            "d" | "day" | "days" => Span::Fixed(Duration::days(amount_i64)),
+            "w" | "week" | "weeks" => Span::Fixed(Duration::weeks(amount_i64)),
+            "mo" | "month" | "months" => Span::Months(amount),
+            "y" | "year" | "years" => Span::Months(amount.checked_mul(12)?),
+            _ => return None,
+        });
+    }
+    Some(spans)
+}
+
+fn add_duration<Tz: TimeZone>(now: DateTime<Tz>, words: &[&str]) -> Option<DateTime<Tz>> {
+    let mut datetime = now;
+    for span in parse_spans(words)? {
+        datetime = match span {
+            Span::Fixed(duration) => datetime + duration,
+            Span::Months(months) => add_months(datetime, months)?,
+        };
+    }
+    Some(datetime)
+}
+
+///Fixed length duration like "2h" or "1 day 6 hours", months and years are refused
+pub fn parse_duration(text: &str) -> Option<Duration> {
+    let text = text.trim().to_lowercase();
+    let words: Vec<&str> = text.split_whitespace().collect();
+    parse_spans(&words)?
+        .into_iter()
+        .try_fold(Duration::zero(), |total, span| match span {
+            Span::Fixed(duration) => Some(total + duration),
+            Span::Months(_) => None,
+        })
+}
+
+fn add_months<Tz: TimeZone>(datetime: DateTime<Tz>, months: u32) -> Option<DateTime<Tz>> {
+    let local = datetime.naive_local();
+    let date = local.date().checked_add_months(Months::new(months))?;
+    datetime
+        .timezone()
+        .from_local_datetime(&date.and_time(local.time()))
+        .earliest()
+}
+
+///An optional day followed by an optional time, "tomorrow at 9am", "friday", "14:30"
+fn parse_date_and_time<Tz: TimeZone>(now: DateTime<Tz>, words: &[&str]) -> Option<DateTime<Tz>> {
+    let today = now.naive_local().date();
+    let (date, words) = parse_day(today, words).unwrap_or((today, words));
+    let words = match words {
+        ["at", rest @ ..] => rest,
+        _ => words,
+    };
+    let time = match words {
+        [] => NaiveTime::from_hms_opt(0, 0, 0)?,
+        _ => parse_time(&words.concat())?,
+    };
+
+    now.timezone()
+        .from_local_datetime(&date.and_time(time))
+        .earliest()
+}
+
+fn parse_day<'a>(today: NaiveDate, words: &'a [&'a str]) -> Option<(NaiveDate, &'a [&'a str])> {
+    let (word, rest) = words.split_first()?;
+    let date = match *word {
+        "today" => today,
+        "tomorrow" => today.succ_opt()?,
+        "yesterday" => today.pred_opt()?,
+        "next" | "this" | "on" => {
+            let (day, rest) = rest.split_first()?;
+            return Some((next_weekday(today, day.parse().ok()?), rest));
+        }
+        word => match word.parse::<Weekday>() {
+            Ok(weekday) => next_weekday(today, weekday),
+            Err(_) => DATE_FORMATS
+                .iter()
+                .find_map(|format| NaiveDate::parse_from_str(word, format).ok())?,
+        },
+    };
+    Some((date, rest))
+}
+
+///The first such weekday after today, a week ahead when today is that day
+fn next_weekday(today: NaiveDate, weekday: Weekday) -> NaiveDate {
+    let days = (7 + weekday.num_days_from_monday() - today.weekday().num_days_from_monday()) % 7;
+    let days = match days {
+        0 => 7,
+        days => days,
+    };
+    today + Duration::days(days.into())
+}
+
+///"9am", "9:30pm", "14:30", "14:30:15", "noon", "midnight"
+fn parse_time(text: &str) -> Option<NaiveTime> {
+    match text {
+        "noon" => return NaiveTime::from_hms_opt(12, 0, 0),
+        "midnight" => return NaiveTime::from_hms_opt(0, 0, 0),
+        _ => {}
+    }
+
+    let (clock, offset) = match (text.strip_suffix("am"), text.strip_suffix("pm")) {
+        (Some(clock), _) => (clock, Some(0)),
+        (_, Some(clock)) => (clock, Some(12)),
+        _ => (text, None),
+    };
+    let mut parts = clock.split(':').map(|x| x.parse::<u32>().ok());
+    let hour = parts.next()??;
+    let minute = parts.next().unwrap_or(Some(0))?;
+    let second = parts.next().unwrap_or(Some(0))?;
+    if parts.next().is_some() {
+        return None;
+    }
+
+    let hour = match offset {
+        Some(offset) if (1..=12).contains(&hour) => hour % 12 + offset,
+        Some(_) => return None,
+        None => hour,
+    };
+    NaiveTime::from_hms_opt(hour, minute, second)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::FixedOffset;
+
+    //Wednesday
+    const NOW: &str = "2026-10-14 10:20:00";
+
+    fn at(text: &str) -> DateTime<Utc> {
+        Utc.datetime_from_str(text, "%Y-%m-%d %H:%M:%S").unwrap()
+    }
+
+    fn parse(text: &str) -> Option<DateTime<Utc>> {
+        parse_datetime_from(text, at(NOW))
+    }
+
+    #[test]
+    fn absolute_dates_and_times() {
+        assert_eq!(parse("20261101"), Some(at("2026-11-01 00:00:00")));
+        assert_eq!(parse("20261101 14:30"), Some(at("2026-11-01 14:30:00")));
+        assert_eq!(
+            parse("2026-11-01 at 2:30pm"),
+            Some(at("2026-11-01 14:30:00"))
+        );
+        assert_eq!(
+            parse("2026-11-01T14:30:15"),
+            Some(at("2026-11-01 14:30:15"))
+        );
+        assert_eq!(parse("20261132"), None);
+    }
+
+    #[test]
+    fn times_of_day() {
+        assert_eq!(parse("14:30"), Some(at("2026-10-14 14:30:00")));
+        assert_eq!(parse("9am"), Some(at("2026-10-14 09:00:00")));
+        assert_eq!(parse("9 PM"), Some(at("2026-10-14 21:00:00")));
+        assert_eq!(parse("12am"), Some(at("2026-10-14 00:00:00")));
+        assert_eq!(parse("noon"), Some(at("2026-10-14 12:00:00")));
+        assert_eq!(parse("13pm"), None);
+        assert_eq!(parse("25:00"), None);
+    }
+
+    #[test]
+    fn named_days() {
+        assert_eq!(parse("now"), Some(at(NOW)));
+        assert_eq!(parse("today"), Some(at("2026-10-14 00:00:00")));
+        assert_eq!(parse("tomorrow 9am"), Some(at("2026-10-15 09:00:00")));
+        assert_eq!(parse("yesterday at noon"), Some(at("2026-10-13 12:00:00")));
+        assert_eq!(parse("friday"), Some(at("2026-10-16 00:00:00")));
+        assert_eq!(parse("next fri 8:15"), Some(at("2026-10-16 08:15:00")));
+        assert_eq!(parse("next wednesday"), Some(at("2026-10-21 00:00:00")));
+        assert_eq!(parse("next week"), None);
+    }
+
+    #[test]
+    fn relative_durations() {
+        assert_eq!(parse("in 3 days"), Some(at("2026-10-17 10:20:00")));
+        assert_eq!(parse("in an hour"), Some(at("2026-10-14 11:20:00")));
+        assert_eq!(
+            parse("in 1 hour and 30 minutes"),
+            Some(at("2026-10-14 11:50:00"))
+        );
+        assert_eq!(parse("+2h"), Some(at("2026-10-14 12:20:00")));
+        assert_eq!(parse("+1w2d"), Some(at("2026-10-23 10:20:00")));
+        assert_eq!(parse("in 1 month"), Some(at("2026-11-14 10:20:00")));
+        assert_eq!(parse("in 3 fortnights"), None);
+        assert_eq!(parse("in"), None);
+        assert_eq!(parse("+"), None);
+    }
+
+    #[test]
+    fn durations_and_reminder_offsets() {
+        assert_eq!(parse_duration("1 day 6 hours"), Some(Duration::hours(30)));
+        assert_eq!(parse_duration("90m"), Some(Duration::minutes(90)));
+        assert_eq!(parse_duration("1 month"), None);
+        assert_eq!(
+            parse_reminder_time("1h before").unwrap(),
+            ReminderTime::BeforeDue(Duration::hours(1))
+        );
+        assert_eq!(
+            parse_reminder_time("2 days before due").unwrap(),
+            ReminderTime::BeforeDue(Duration::days(2))
+        );
+        assert_eq!(
+            parse_reminder_time("at due").unwrap(),
+            ReminderTime::BeforeDue(Duration::zero())
+        );
+        assert!(matches!(
+            parse_reminder_time("tomorrow 9am"),
+            Ok(ReminderTime::At(_))
+        ));
+        assert!(parse_reminder_time("1 month before").is_err());
+    }
+
+    #[test]
+    fn relative_formatting() {
+        let now = at(NOW);
+        let format = |minutes| format_relative(&(now + Duration::minutes(minutes)), &now);
+        assert_eq!(format(0), "now");
+        assert_eq!(format(45), "in 45m");
+        assert_eq!(format(3 * 60 + 59), "in 3h");
+        assert_eq!(format(-2 * 24 * 60), "2d ago");
+        assert_eq!(format(30 * 24 * 60), "in 4w");
+    }
+
+    #[test]
+    fn local_times_are_read_in_the_given_timezone() {
+        let tz = FixedOffset::east(2 * 3600);
+        let now = at(NOW).with_timezone(&tz);
+
+        let parsed = parse_datetime_from("tomorrow 9am", now).unwrap();
+        assert_eq!(parsed.with_timezone(&Utc), at("2026-10-15 07:00:00"));
+    }
+}
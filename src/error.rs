@@ -0,0 +1,41 @@
+use thiserror::Error;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Database error: {0}")]
+    Database(#[from] rusqlite::Error),
+    #[error("Migration '{description}' failed: {source}")]
+    Migration {
+        description: &'static str,
+        source: rusqlite::Error,
+    },
+    #[error("Database schema version {found} is newer than version {supported} supported by this binary")]
+    SchemaTooNew { found: u32, supported: u32 },
+    #[error("Terminal error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("CSV error: {0}")]
+    Csv(#[from] csv::Error),
+    #[error("Input cancelled")]
+    InputCancelled,
+    #[error("{0} not found")]
+    NotFound(String),
+    #[error("{0}")]
+    Validation(String),
+}
+
+impl Error {
+    ///Errors the interactive menu cannot recover from
+    pub fn is_fatal(&self) -> bool {
+        matches!(
+            self,
+            Error::Database(_)
+                | Error::Migration { .. }
+                | Error::SchemaTooNew { .. }
+                | Error::Io(_)
+        )
+    }
+}
@@ -0,0 +1,310 @@
+use crate::{
+    error::Result,
+    store::{TaskFilter, TaskStore},
+    Comment, Priority, Reminder, Task,
+};
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+///Bumped when a field changes meaning, so imports can refuse newer files
+pub const EXPORT_VERSION: u32 = 1;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ExportFormat {
+    ///A single document with comments and reminders nested in their task
+    #[default]
+    Json,
+    ///One file per table in the output directory
+    Csv,
+}
+
+///Boards with their columns and tasks, as written by the JSON export
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Export {
+    pub version: u32,
+    pub exported_at: DateTime<Utc>,
+    pub boards: Vec<BoardExport>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BoardExport {
+    pub id: u16,
+    pub title: String,
+    pub columns: Vec<ColumnExport>,
+    pub tasks: Vec<TaskExport>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ColumnExport {
+    pub id: u16,
+    pub title: String,
+    pub position: u16,
+    pub terminal: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TaskExport {
+    pub id: u16,
+    pub title: String,
+    pub done: bool,
+    pub column: String,
+    pub priority: Priority,
+    pub created_at: DateTime<Utc>,
+    #[serde(default)]
+    pub completed_at: Option<DateTime<Utc>>,
+    pub due_at: Option<DateTime<Utc>>,
+    ///RRULE text
+    pub recurrence: Option<String>,
+    pub parent_id: Option<u16>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    ///Ids of the tasks this one depends on
+    #[serde(default)]
+    pub blocked_by: Vec<u16>,
+    #[serde(default)]
+    pub reminders: Vec<ReminderExport>,
+    #[serde(default)]
+    pub comments: Vec<CommentExport>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReminderExport {
+    pub id: u16,
+    pub remind_at: DateTime<Utc>,
+    ///Seconds before the due date, for reminders following it
+    pub before_due: Option<i64>,
+    pub fired_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CommentExport {
+    pub id: u16,
+    pub title: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<Reminder> for ReminderExport {
+    fn from(reminder: Reminder) -> ReminderExport {
+        ReminderExport {
+            id: reminder.id,
+            remind_at: reminder.remind_at,
+            before_due: reminder.before_due.map(|x| x.num_seconds()),
+            fired_at: reminder.fired_at,
+        }
+    }
+}
+
+impl From<Comment> for CommentExport {
+    fn from(comment: Comment) -> CommentExport {
+        CommentExport {
+            id: comment.id,
+            title: comment.title,
+            created_at: comment.created_at,
+        }
+    }
+}
+
+fn export_task(store: &dyn TaskStore, task: Task) -> Result<TaskExport> {
+    Ok(TaskExport {
+        blocked_by: store.list_blockers(task.id)?.iter().map(|x| x.id).collect(),
+        reminders: store
+            .list_reminders(task.id)?
+            .into_iter()
+            .map(ReminderExport::from)
+            .collect(),
+        comments: store
+            .list_comments(task.id)?
+            .into_iter()
+            .map(CommentExport::from)
+            .collect(),
+        id: task.id,
+        title: task.title,
+        done: task.done == 1,
+        column: task.column,
+        priority: task.priority,
+        created_at: task.created_at,
+        completed_at: task.completed_at,
+        due_at: task.due_at,
+        recurrence: task.recurrence.map(|x| x.to_string()),
+        parent_id: task.parent_id,
+        tags: task.tags,
+    })
+}
+
+///Tasks matching the filter grouped by board. Every board is exported unless the filter
+///names some, even those without matching tasks
+pub fn collect(store: &dyn TaskStore, filter: &TaskFilter) -> Result<Export> {
+    let mut tasks = store.list_tasks(filter)?;
+    let mut boards = Vec::new();
+    for board in store.list_boards()? {
+        if !filter.board_ids.is_empty() && !filter.board_ids.contains(&board.id) {
+            continue;
+        }
+        let columns = store
+            .list_columns(board.id)?
+            .into_iter()
+            .map(|x| ColumnExport {
+                id: x.id,
+                title: x.title,
+                position: x.position,
+                terminal: x.terminal,
+            })
+            .collect();
+        let (own, rest): (Vec<Task>, Vec<Task>) =
+            tasks.into_iter().partition(|x| x.board_id == board.id);
+        tasks = rest;
+        boards.push(BoardExport {
+            id: board.id,
+            title: board.title,
+            columns,
+            tasks: own
+                .into_iter()
+                .map(|x| export_task(store, x))
+                .collect::<Result<_>>()?,
+        });
+    }
+    Ok(Export {
+        version: EXPORT_VERSION,
+        exported_at: Utc::now(),
+        boards,
+    })
+}
+
+pub fn write_json(export: &Export, writer: impl Write) -> Result<()> {
+    serde_json::to_writer_pretty(writer, export)?;
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct BoardRow<'a> {
+    id: u16,
+    title: &'a str,
+}
+
+#[derive(Serialize)]
+struct ColumnRow<'a> {
+    id: u16,
+    board_id: u16,
+    title: &'a str,
+    position: u16,
+    terminal: bool,
+}
+
+#[derive(Serialize)]
+struct TaskRow<'a> {
+    id: u16,
+    board_id: u16,
+    title: &'a str,
+    done: bool,
+    column: &'a str,
+    priority: Priority,
+    created_at: DateTime<Utc>,
+    completed_at: Option<DateTime<Utc>>,
+    due_at: Option<DateTime<Utc>>,
+    recurrence: Option<&'a str>,
+    parent_id: Option<u16>,
+    ///Space separated
+    tags: String,
+    ///Space separated ids
+    blocked_by: String,
+}
+
+#[derive(Serialize)]
+struct CommentRow<'a> {
+    id: u16,
+    task_id: u16,
+    title: &'a str,
+    created_at: DateTime<Utc>,
+}
+
+#[derive(Serialize)]
+struct ReminderRow {
+    id: u16,
+    task_id: u16,
+    remind_at: DateTime<Utc>,
+    before_due: Option<i64>,
+    fired_at: Option<DateTime<Utc>>,
+}
+
+///boards.csv, columns.csv, tasks.csv, comments.csv and reminders.csv in the directory,
+///created if needed. Returns the written paths
+pub fn write_csv(export: &Export, directory: &Path) -> Result<Vec<PathBuf>> {
+    fs::create_dir_all(directory)?;
+    let paths: Vec<PathBuf> = ["boards", "columns", "tasks", "comments", "reminders"]
+        .iter()
+        .map(|x| directory.join(format!("{}.csv", x)))
+        .collect();
+    let mut boards = csv::Writer::from_path(&paths[0])?;
+    let mut columns = csv::Writer::from_path(&paths[1])?;
+    let mut tasks = csv::Writer::from_path(&paths[2])?;
+    let mut comments = csv::Writer::from_path(&paths[3])?;
+    let mut reminders = csv::Writer::from_path(&paths[4])?;
+
+    for board in &export.boards {
+        boards.serialize(BoardRow {
+            id: board.id,
+            title: &board.title,
+        })?;
+        for column in &board.columns {
+            columns.serialize(ColumnRow {
+                id: column.id,
+                board_id: board.id,
+                title: &column.title,
+                position: column.position,
+                terminal: column.terminal,
+            })?;
+        }
+        for task in &board.tasks {
+            let blocked_by: Vec<String> = task.blocked_by.iter().map(u16::to_string).collect();
+            tasks.serialize(TaskRow {
+                id: task.id,
+                board_id: board.id,
+                title: &task.title,
+                done: task.done,
+                column: &task.column,
+                priority: task.priority,
+                created_at: task.created_at,
+                completed_at: task.completed_at,
+                due_at: task.due_at,
+                recurrence: task.recurrence.as_deref(),
+                parent_id: task.parent_id,
+                tags: task.tags.join(" "),
+                blocked_by: blocked_by.join(" "),
+            })?;
+            for comment in &task.comments {
+                comments.serialize(CommentRow {
+                    id: comment.id,
+                    task_id: task.id,
+                    title: &comment.title,
+                    created_at: comment.created_at,
+                })?;
+            }
+            for reminder in &task.reminders {
+                reminders.serialize(ReminderRow {
+                    id: reminder.id,
+                    task_id: task.id,
+                    remind_at: reminder.remind_at,
+                    before_due: reminder.before_due,
+                    fired_at: reminder.fired_at,
+                })?;
+            }
+        }
+    }
+
+    for writer in [
+        &mut boards,
+        &mut columns,
+        &mut tasks,
+        &mut comments,
+        &mut reminders,
+    ] {
+        writer.flush()?;
+    }
+    Ok(paths)
+}
@@ -0,0 +1,185 @@
+use crate::{tags::TagFilter, TABLE_DEPENDENCIES, TABLE_TAGS, TABLE_TASKS, TABLE_TASK_TAGS};
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use rusqlite::ToSql;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum TaskSort {
+    ///Most urgent first
+    #[default]
+    Priority,
+    Id,
+    Title,
+    Created,
+    Due,
+}
+
+///Criteria for listing tasks. Unset fields match every task
+#[derive(Debug, Default, Clone)]
+pub struct TaskFilter {
+    pub done: Option<bool>,
+    ///Any of these boards. Empty matches every board
+    pub board_ids: Vec<u16>,
+    ///Only subtasks of this task
+    pub parent_id: Option<u16>,
+    pub due_before: Option<DateTime<Utc>>,
+    pub due_after: Option<DateTime<Utc>>,
+    ///Only tasks without pending blockers
+    pub ready: bool,
+    pub tags: TagFilter,
+    ///Case insensitive match on the title
+    pub text: Option<String>,
+    pub created_after: Option<DateTime<Utc>>,
+    pub created_before: Option<DateTime<Utc>>,
+    ///Tasks without completion time never match
+    pub completed_after: Option<DateTime<Utc>>,
+    pub completed_before: Option<DateTime<Utc>>,
+    pub sort: TaskSort,
+    pub descending: bool,
+    pub limit: Option<u32>,
+    pub offset: Option<u32>,
+}
+
+impl TaskFilter {
+    pub fn pending() -> TaskFilter {
+        TaskFilter::done(false)
+    }
+
+    pub fn done(done: bool) -> TaskFilter {
+        TaskFilter {
+            done: Some(done),
+            ..Default::default()
+        }
+    }
+
+    pub fn board(mut self, board_id: u16) -> TaskFilter {
+        self.board_ids.push(board_id);
+        self
+    }
+
+    pub fn parent(mut self, parent_id: u16) -> TaskFilter {
+        self.parent_id = Some(parent_id);
+        self
+    }
+
+    ///WHERE, ORDER BY, LIMIT and OFFSET clauses with their bound parameters
+    pub fn to_sql(&self) -> (String, Vec<Box<dyn ToSql>>) {
+        let mut conditions: Vec<String> = Vec::new();
+        let mut params: Vec<Box<dyn ToSql>> = Vec::new();
+
+        if let Some(done) = self.done {
+            params.push(Box::new(done));
+            conditions.push(format!("{TABLE_TASKS}.done = ?{}", params.len()));
+        }
+
+        if !self.board_ids.is_empty() {
+            let mut placeholders: Vec<String> = Vec::new();
+            for board_id in &self.board_ids {
+                params.push(Box::new(*board_id));
+                placeholders.push(format!("?{}", params.len()));
+            }
+            conditions.push(format!(
+                "{TABLE_TASKS}.board_id IN ({})",
+                placeholders.join(", ")
+            ));
+        }
+
+        if let Some(parent_id) = self.parent_id {
+            params.push(Box::new(parent_id));
+            conditions.push(format!("{TABLE_TASKS}.parent_id = ?{}", params.len()));
+        }
+
+        if self.ready {
+            conditions.push(format!(
+                "NOT EXISTS (SELECT 1 FROM {TABLE_DEPENDENCIES}
+                JOIN {TABLE_TASKS} AS blockers ON blockers.id = {TABLE_DEPENDENCIES}.blocker_id
+                WHERE {TABLE_DEPENDENCIES}.task_id = {TABLE_TASKS}.id AND blockers.done = 0)"
+            ));
+        }
+
+        let tag_conditions = [
+            ("EXISTS", &self.tags.include),
+            ("NOT EXISTS", &self.tags.exclude),
+        ];
+        for (operator, names) in tag_conditions {
+            for name in names {
+                params.push(Box::new(name.clone()));
+                conditions.push(format!(
+                    "{operator} (SELECT 1 FROM {TABLE_TASK_TAGS}
+                    JOIN {TABLE_TAGS} ON {TABLE_TAGS}.id = {TABLE_TASK_TAGS}.tag_id
+                    WHERE {TABLE_TASK_TAGS}.task_id = {TABLE_TASKS}.id AND {TABLE_TAGS}.name = ?{})",
+                    params.len()
+                ));
+            }
+        }
+
+        let datetime_ranges = [
+            ("due_at", "<", self.due_before),
+            ("due_at", ">", self.due_after),
+            ("created_at", "<", self.created_before),
+            ("created_at", ">=", self.created_after),
+            ("completed_at", "<", self.completed_before),
+            ("completed_at", ">=", self.completed_after),
+        ];
+        for (column, operator, datetime) in datetime_ranges {
+            if let Some(datetime) = datetime {
+                params.push(Box::new(datetime));
+                conditions.push(format!(
+                    "{TABLE_TASKS}.{column} {operator} ?{}",
+                    params.len()
+                ));
+            }
+        }
+
+        if let Some(text) = &self.text {
+            params.push(Box::new(format!("%{}%", escape_like(text))));
+            conditions.push(format!(
+                "{TABLE_TASKS}.title LIKE ?{} ESCAPE '\\'",
+                params.len()
+            ));
+        }
+
+        let mut sql = String::new();
+        if !conditions.is_empty() {
+            sql.push_str(&format!(" WHERE {}", conditions.join(" AND ")));
+        }
+
+        let direction = match self.descending {
+            true => "DESC",
+            false => "ASC",
+        };
+        let order = match self.sort {
+            TaskSort::Priority => {
+                let direction = match self.descending {
+                    true => "ASC",
+                    false => "DESC",
+                };
+                format!("{TABLE_TASKS}.priority {direction}")
+            }
+            TaskSort::Id => format!("{TABLE_TASKS}.id {direction}"),
+            TaskSort::Title => format!("{TABLE_TASKS}.title COLLATE NOCASE {direction}"),
+            TaskSort::Created => format!("{TABLE_TASKS}.created_at {direction}"),
+            //Tasks without due date go last
+            TaskSort::Due => {
+                format!("{TABLE_TASKS}.due_at IS NULL, {TABLE_TASKS}.due_at {direction}")
+            }
+        };
+        sql.push_str(&format!(" ORDER BY {order}, {TABLE_TASKS}.id {direction}"));
+
+        //SQLite only accepts OFFSET after a LIMIT, -1 meaning no limit
+        if self.limit.is_some() || self.offset.is_some() {
+            params.push(Box::new(self.limit.map_or(-1, |x| x as i64)));
+            sql.push_str(&format!(" LIMIT ?{}", params.len()));
+            params.push(Box::new(self.offset.unwrap_or(0)));
+            sql.push_str(&format!(" OFFSET ?{}", params.len()));
+        }
+
+        (sql, params)
+    }
+}
+
+fn escape_like(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
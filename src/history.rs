@@ -0,0 +1,97 @@
+use crate::dates::format_datetime;
+use chrono::{DateTime, Utc};
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, ValueRef};
+use std::fmt;
+use tabled::Tabled;
+
+///Fields of a task recorded in its history when they change
+pub const FIELD_TITLE: &str = "title";
+pub const FIELD_PRIORITY: &str = "priority";
+pub const FIELD_DUE: &str = "due";
+pub const FIELD_RECURRENCE: &str = "repeats";
+pub const FIELD_PARENT: &str = "parent";
+pub const FIELD_BOARD: &str = "board";
+pub const FIELD_COLUMN: &str = "column";
+pub const FIELD_STATUS: &str = "status";
+pub const FIELD_TAGS: &str = "tags";
+pub const FIELD_REMINDER: &str = "reminder";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HistoryAction {
+    Created,
+    Edited,
+    ///Completed or reopened
+    Status,
+    Reminder,
+    Deleted,
+}
+
+const HISTORY_ACTIONS: [(HistoryAction, &str); 5] = [
+    (HistoryAction::Created, "created"),
+    (HistoryAction::Edited, "edited"),
+    (HistoryAction::Status, "status"),
+    (HistoryAction::Reminder, "reminder"),
+    (HistoryAction::Deleted, "deleted"),
+];
+
+impl fmt::Display for HistoryAction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = HISTORY_ACTIONS
+            .iter()
+            .find(|x| x.0 == *self)
+            .map(|x| x.1)
+            .unwrap_or_default();
+        write!(f, "{}", name)
+    }
+}
+
+impl ToSql for HistoryAction {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.to_string()))
+    }
+}
+
+impl FromSql for HistoryAction {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        let name = value.as_str()?;
+        HISTORY_ACTIONS
+            .iter()
+            .find(|x| x.1 == name)
+            .map(|x| x.0)
+            .ok_or_else(|| FromSqlError::Other(format!("Unknown history action {}", name).into()))
+    }
+}
+
+///"done" or "pending"
+pub fn status_name(done: bool) -> &'static str {
+    match done {
+        true => "done",
+        false => "pending",
+    }
+}
+
+fn display_value(value: &Option<String>) -> String {
+    value.clone().unwrap_or_default()
+}
+
+fn display_datetime(datetime: &DateTime<Utc>) -> String {
+    format_datetime(datetime)
+}
+
+///Change of a task, kept after the task is deleted
+#[derive(Debug, Clone, Tabled)]
+pub struct HistoryEntry {
+    #[tabled(skip)]
+    pub id: i64,
+    #[tabled(skip)]
+    pub task_id: u16,
+    #[tabled(rename = "at", display_with = "display_datetime")]
+    pub created_at: DateTime<Utc>,
+    pub action: HistoryAction,
+    ///Empty for creations and deletions
+    pub field: String,
+    #[tabled(rename = "before", display_with = "display_value")]
+    pub old_value: Option<String>,
+    #[tabled(rename = "after", display_with = "display_value")]
+    pub new_value: Option<String>,
+}
@@ -0,0 +1,595 @@
+use crate::{
+    dates::{parse_datetime, ReminderTime},
+    error::{Error, Result},
+    export::{Export, EXPORT_VERSION},
+    recurrence::Recurrence,
+    store::{NewTask, TaskFilter, TaskStore},
+    tags::validate_tag_name,
+    Priority,
+};
+use chrono::{DateTime, Duration, NaiveDate, TimeZone, Utc};
+use clap::ValueEnum;
+use std::{collections::HashMap, fmt, io::Read, path::Path, str::FromStr};
+use tabled::Tabled;
+
+///Board of the imported tasks that don't name one
+pub const DEFAULT_IMPORT_BOARD: &str = "imported";
+///Shown to the user when asking for a CSV column mapping
+pub const MAPPING_EXAMPLES: &str = "title=Summary,due=Deadline";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ImportFormat {
+    ///The JSON export of this crate
+    Json,
+    ///A header line then one task per line, see --map
+    Csv,
+    ///One task per line, e.g. "(A) Call Anna +work @phone due:2024-05-01"
+    Todotxt,
+}
+
+impl ImportFormat {
+    ///From the extension of the file, .txt being todo.txt
+    pub fn from_path(path: &Path) -> Option<ImportFormat> {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+        match extension.as_str() {
+            "json" => Some(ImportFormat::Json),
+            "csv" => Some(ImportFormat::Csv),
+            "txt" => Some(ImportFormat::Todotxt),
+            _ => None,
+        }
+    }
+}
+
+///A task read from a file, before it gets a board and an id
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ImportTask {
+    ///Id in the file, used by `parent_id` and `blocked_by`
+    pub source_id: Option<u16>,
+    ///Board title, the default board when None
+    pub board: Option<String>,
+    pub title: String,
+    pub done: bool,
+    ///Column title, ignored when the board has no such column
+    pub column: Option<String>,
+    pub priority: Priority,
+    pub created_at: Option<DateTime<Utc>>,
+    ///Only kept for done tasks
+    pub completed_at: Option<DateTime<Utc>>,
+    pub due_at: Option<DateTime<Utc>>,
+    pub recurrence: Option<Recurrence>,
+    pub parent_id: Option<u16>,
+    pub tags: Vec<String>,
+    pub blocked_by: Vec<u16>,
+    pub reminders: Vec<ReminderTime>,
+    pub comments: Vec<String>,
+}
+
+impl ImportTask {
+    fn new(title: &str) -> ImportTask {
+        ImportTask {
+            title: title.to_string(),
+            ..Default::default()
+        }
+    }
+}
+
+///Tasks of a JSON export. Comments get the import date and fired reminders are left out
+pub fn read_json(reader: impl Read) -> Result<Vec<ImportTask>> {
+    let export: Export = serde_json::from_reader(reader)?;
+    if export.version > EXPORT_VERSION {
+        return Err(Error::Validation(format!(
+            "Export version {} is newer than version {} supported by this binary",
+            export.version, EXPORT_VERSION
+        )));
+    }
+
+    let mut tasks = Vec::new();
+    for board in export.boards {
+        for task in board.tasks {
+            let recurrence = match task.recurrence {
+                Some(text) => Some(text.parse()?),
+                None => None,
+            };
+            tasks.push(ImportTask {
+                source_id: Some(task.id),
+                board: Some(board.title.clone()),
+                title: task.title,
+                done: task.done,
+                column: Some(task.column),
+                priority: task.priority,
+                created_at: Some(task.created_at),
+                completed_at: task.completed_at,
+                due_at: task.due_at,
+                recurrence,
+                parent_id: task.parent_id,
+                tags: task.tags,
+                blocked_by: task.blocked_by,
+                reminders: task
+                    .reminders
+                    .into_iter()
+                    .filter(|x| x.fired_at.is_none())
+                    .map(|x| match x.before_due {
+                        Some(seconds) => ReminderTime::BeforeDue(Duration::seconds(seconds)),
+                        None => ReminderTime::At(x.remind_at),
+                    })
+                    .collect(),
+                comments: task.comments.into_iter().map(|x| x.title).collect(),
+            });
+        }
+    }
+    Ok(tasks)
+}
+
+///Task field a CSV column can fill
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    Title,
+    Board,
+    Done,
+    Column,
+    Priority,
+    Due,
+    Created,
+    Completed,
+    Recurrence,
+    Tags,
+}
+
+///Each field with the header it is read from by default, those of the CSV export
+const FIELDS: [(Field, &str, &str); 10] = [
+    (Field::Title, "title", "title"),
+    (Field::Board, "board", "board"),
+    (Field::Done, "done", "done"),
+    (Field::Column, "column", "column"),
+    (Field::Priority, "priority", "priority"),
+    (Field::Due, "due", "due_at"),
+    (Field::Created, "created", "created_at"),
+    (Field::Completed, "completed", "completed_at"),
+    (Field::Recurrence, "recurrence", "recurrence"),
+    (Field::Tags, "tags", "tags"),
+];
+
+///CSV header of each field, parsed from "title=Summary,due=Deadline". Fields left out keep
+///their default header, and headers are compared case insensitively
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnMapping {
+    headers: Vec<(Field, String)>,
+}
+
+impl Default for ColumnMapping {
+    fn default() -> ColumnMapping {
+        ColumnMapping {
+            headers: FIELDS
+                .iter()
+                .map(|(field, _, header)| (*field, header.to_string()))
+                .collect(),
+        }
+    }
+}
+
+impl ColumnMapping {
+    pub fn header(&self, field: Field) -> &str {
+        self.headers
+            .iter()
+            .find(|x| x.0 == field)
+            .map(|x| x.1.as_str())
+            .unwrap_or_default()
+    }
+}
+
+impl FromStr for ColumnMapping {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<ColumnMapping> {
+        let mut mapping = ColumnMapping::default();
+        for pair in text.split(',').filter(|x| !x.trim().is_empty()) {
+            let invalid = || {
+                Error::Validation(format!(
+                    "Invalid mapping {} (try {})",
+                    pair, MAPPING_EXAMPLES
+                ))
+            };
+            let (name, header) = pair.split_once('=').ok_or_else(invalid)?;
+            let field = FIELDS
+                .iter()
+                .find(|x| x.1 == name.trim().to_lowercase())
+                .map(|x| x.0)
+                .ok_or_else(invalid)?;
+            for entry in mapping.headers.iter_mut().filter(|x| x.0 == field) {
+                entry.1 = header.trim().to_string();
+            }
+        }
+        Ok(mapping)
+    }
+}
+
+///RFC 3339, as exported, or any date parse_datetime accepts
+fn parse_import_date(text: &str) -> Result<DateTime<Utc>> {
+    match DateTime::parse_from_rfc3339(text.trim()) {
+        Ok(datetime) => Ok(datetime.with_timezone(&Utc)),
+        Err(_) => parse_datetime(text),
+    }
+}
+
+fn parse_done(text: &str) -> bool {
+    matches!(
+        text.trim().to_lowercase().as_str(),
+        "true" | "1" | "yes" | "x" | "done"
+    )
+}
+
+///Space or comma separated
+fn parse_tags(text: &str) -> Result<Vec<String>> {
+    text.split(|x: char| x.is_whitespace() || x == ',')
+        .filter(|x| !x.is_empty())
+        .map(validate_tag_name)
+        .collect()
+}
+
+///Tasks of a CSV file with a header line. Only the title column is required
+pub fn read_csv(reader: impl Read, mapping: &ColumnMapping) -> Result<Vec<ImportTask>> {
+    let mut reader = csv::Reader::from_reader(reader);
+    let headers: Vec<String> = reader
+        .headers()?
+        .iter()
+        .map(|x| x.trim().to_lowercase())
+        .collect();
+    let index = |field: Field| {
+        let header = mapping.header(field).to_lowercase();
+        headers.iter().position(|x| *x == header)
+    };
+    let title = index(Field::Title).ok_or_else(|| {
+        Error::Validation(format!(
+            "No {} column for the task titles (try --map {})",
+            mapping.header(Field::Title),
+            MAPPING_EXAMPLES
+        ))
+    })?;
+    let indexes: Vec<(Field, usize)> = FIELDS
+        .iter()
+        .filter_map(|(field, _, _)| index(*field).map(|x| (*field, x)))
+        .collect();
+
+    let mut tasks = Vec::new();
+    for (line, record) in reader.records().enumerate() {
+        let record = record?;
+        let at_line = |error: Error| Error::Validation(format!("Line {}: {}", line + 2, error));
+        let mut task = ImportTask::new(record.get(title).unwrap_or_default().trim());
+        if task.title.is_empty() {
+            return Err(at_line(Error::Validation("missing title".to_string())));
+        }
+        for (field, index) in &indexes {
+            let value = record.get(*index).unwrap_or_default().trim();
+            if value.is_empty() {
+                continue;
+            }
+            match field {
+                Field::Title => (),
+                Field::Board => task.board = Some(value.to_string()),
+                Field::Done => task.done = parse_done(value),
+                Field::Column => task.column = Some(value.to_string()),
+                Field::Priority => {
+                    task.priority = Priority::from_str(value, true).map_err(|_| {
+                        at_line(Error::Validation(format!("Invalid priority {}", value)))
+                    })?
+                }
+                Field::Due => task.due_at = Some(parse_import_date(value).map_err(at_line)?),
+                Field::Created => {
+                    task.created_at = Some(parse_import_date(value).map_err(at_line)?)
+                }
+                Field::Completed => {
+                    task.completed_at = Some(parse_import_date(value).map_err(at_line)?)
+                }
+                Field::Recurrence => task.recurrence = Some(value.parse().map_err(at_line)?),
+                Field::Tags => task.tags = parse_tags(value).map_err(at_line)?,
+            }
+        }
+        tasks.push(task);
+    }
+    Ok(tasks)
+}
+
+///None when the word isn't shaped like YYYY-MM-DD, an error when it is but isn't a valid date
+fn parse_todotxt_date(word: &str) -> Result<Option<DateTime<Utc>>> {
+    let shaped = word.len() == 10
+        && word.chars().enumerate().all(|(i, x)| match i {
+            4 | 7 => x == '-',
+            _ => x.is_ascii_digit(),
+        });
+    if !shaped {
+        return Ok(None);
+    }
+    NaiveDate::parse_from_str(word, "%Y-%m-%d")
+        .ok()
+        .and_then(|x| x.and_hms_opt(0, 0, 0))
+        .map(|x| Some(Utc.from_utc_datetime(&x)))
+        .ok_or_else(|| Error::Validation(format!("Invalid date {}", word)))
+}
+
+///A is urgent, B high, C normal and anything after low
+fn parse_todotxt_priority(word: &str) -> Option<Priority> {
+    let letter = word.strip_prefix('(')?.strip_suffix(')')?;
+    match letter {
+        "A" => Some(Priority::Urgent),
+        "B" => Some(Priority::High),
+        "C" => Some(Priority::Normal),
+        _ if letter.len() == 1 && letter.chars().all(|x| x.is_ascii_uppercase()) => {
+            Some(Priority::Low)
+        }
+        _ => None,
+    }
+}
+
+///A todo.txt line, None when blank. The first +project is the board, other projects and
+///@contexts are tags, and due: sets the due date. Other key:value pairs stay in the title
+pub fn parse_todotxt_line(line: &str) -> Result<Option<ImportTask>> {
+    let mut words = line.split_whitespace().peekable();
+    let mut task = ImportTask::default();
+    if words.peek().is_none() {
+        return Ok(None);
+    }
+
+    if words.peek() == Some(&"x") {
+        task.done = true;
+        words.next();
+    }
+    if let Some(priority) = words.peek().and_then(|x| parse_todotxt_priority(x)) {
+        task.priority = priority;
+        words.next();
+    }
+    //Done tasks have their completion date first
+    if task.done {
+        task.completed_at = match words.peek() {
+            Some(word) => parse_todotxt_date(word)?,
+            None => None,
+        };
+        if task.completed_at.is_some() {
+            words.next();
+        }
+    }
+    let created_at = match words.peek() {
+        Some(word) => parse_todotxt_date(word)?,
+        None => None,
+    };
+    if let Some(created_at) = created_at {
+        task.created_at = Some(created_at);
+        words.next();
+    }
+
+    let mut title = Vec::new();
+    for word in words {
+        match (
+            word.strip_prefix('+'),
+            word.strip_prefix('@'),
+            word.split_once(':'),
+        ) {
+            (Some(project), _, _) if !project.is_empty() && task.board.is_none() => {
+                task.board = Some(project.to_string())
+            }
+            (Some(tag), _, _) | (_, Some(tag), _) if !tag.is_empty() => {
+                task.tags.push(validate_tag_name(tag)?)
+            }
+            (_, _, Some(("due", date))) => {
+                task.due_at = Some(
+                    parse_todotxt_date(date)?
+                        .ok_or_else(|| Error::Validation(format!("Invalid due date {}", date)))?,
+                )
+            }
+            _ => title.push(word),
+        }
+    }
+    task.title = title.join(" ");
+    match task.title.is_empty() {
+        true => Err(Error::Validation(format!("No title in {}", line.trim()))),
+        false => Ok(Some(task)),
+    }
+}
+
+pub fn read_todotxt(mut reader: impl Read) -> Result<Vec<ImportTask>> {
+    let mut text = String::new();
+    reader.read_to_string(&mut text)?;
+    let mut tasks = Vec::new();
+    for (line, text) in text.lines().enumerate() {
+        let task = parse_todotxt_line(text)
+            .map_err(|x| Error::Validation(format!("Line {}: {}", line + 1, x)))?;
+        tasks.extend(task);
+    }
+    Ok(tasks)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportOutcome {
+    Created,
+    ///A task with the same title was already on the board
+    Duplicate,
+}
+
+impl fmt::Display for ImportOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            ImportOutcome::Created => "created",
+            ImportOutcome::Duplicate => "duplicate",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Tabled)]
+pub struct ImportedTask {
+    ///Id of the new task, or of the existing one for duplicates
+    pub id: u16,
+    pub board: String,
+    pub title: String,
+    #[tabled(rename = "result")]
+    pub outcome: ImportOutcome,
+}
+
+#[derive(Debug, Default)]
+pub struct ImportReport {
+    ///Titles of the created boards
+    pub boards: Vec<String>,
+    pub tasks: Vec<ImportedTask>,
+}
+
+impl ImportReport {
+    pub fn count(&self, outcome: ImportOutcome) -> usize {
+        self.tasks.iter().filter(|x| x.outcome == outcome).count()
+    }
+}
+
+///Create the tasks in a single transaction, along with the boards they name. A task whose
+///title is already on its board is skipped. Nothing is saved on error or when `dry_run`
+pub fn import(
+    store: &dyn TaskStore,
+    tasks: &[ImportTask],
+    default_board: &str,
+    dry_run: bool,
+) -> Result<ImportReport> {
+    let mut report = ImportReport::default();
+    store.transaction(!dry_run, &mut || {
+        report = import_tasks(store, tasks, default_board)?;
+        Ok(())
+    })?;
+    Ok(report)
+}
+
+fn import_tasks(
+    store: &dyn TaskStore,
+    tasks: &[ImportTask],
+    default_board: &str,
+) -> Result<ImportReport> {
+    let mut report = ImportReport::default();
+    let mut boards: HashMap<String, u16> = store
+        .list_boards()?
+        .into_iter()
+        .map(|x| (x.title.to_lowercase(), x.id))
+        .collect();
+    let mut titles: HashMap<(u16, String), u16> = store
+        .list_tasks(&TaskFilter::default())?
+        .into_iter()
+        .map(|x| ((x.board_id, x.title), x.id))
+        .collect();
+    //Ids of the file mapped to those of the store
+    let mut ids: HashMap<u16, u16> = HashMap::new();
+    let mut created = Vec::new();
+
+    for task in tasks {
+        let board = task.board.as_deref().unwrap_or(default_board).trim();
+        let board_id = match boards.get(&board.to_lowercase()) {
+            Some(id) => *id,
+            None => {
+                let id = store.add_board(board)?;
+                boards.insert(board.to_lowercase(), id);
+                report.boards.push(board.to_string());
+                id
+            }
+        };
+
+        let key = (board_id, task.title.trim().to_string());
+        if let Some(id) = titles.get(&key) {
+            ids.extend(task.source_id.map(|x| (x, *id)));
+            report.tasks.push(ImportedTask {
+                id: *id,
+                board: board.to_string(),
+                title: key.1,
+                outcome: ImportOutcome::Duplicate,
+            });
+            continue;
+        }
+
+        let columns = store.list_columns(board_id)?;
+        let column = task
+            .column
+            .as_ref()
+            .and_then(|title| columns.iter().find(|x| x.title.eq_ignore_ascii_case(title)))
+            .or_else(|| columns.iter().find(|x| task.done && x.terminal));
+        let id = store.add_task(&NewTask {
+            title: key.1.clone(),
+            board_id,
+            priority: task.priority,
+            due_at: task.due_at,
+            recurrence: task.recurrence.clone(),
+            parent_id: None,
+            column_id: column.map(|x| x.id),
+            created_at: task.created_at,
+            completed_at: task.completed_at,
+        })?;
+        if !task.tags.is_empty() {
+            store.set_task_tags(id, &task.tags)?;
+        }
+        for comment in &task.comments {
+            store.add_comment(id, comment)?;
+        }
+        for reminder in &task.reminders {
+            store.add_reminder(id, *reminder)?;
+        }
+
+        ids.extend(task.source_id.map(|x| (x, id)));
+        titles.insert(key.clone(), id);
+        created.push((id, task));
+        report.tasks.push(ImportedTask {
+            id,
+            board: board.to_string(),
+            title: key.1,
+            outcome: ImportOutcome::Created,
+        });
+    }
+
+    //Relations once every task has its id, those to tasks outside the file are dropped
+    for (id, task) in created {
+        if let Some(parent_id) = task.parent_id.and_then(|x| ids.get(&x)) {
+            let mut saved = store.get_task(id)?;
+            saved.parent_id = Some(*parent_id);
+            store.update_task(&saved)?;
+        }
+        for blocker_id in task.blocked_by.iter().filter_map(|x| ids.get(x)) {
+            store.add_dependency(id, *blocker_id)?;
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn todotxt_lines() {
+        let task =
+            parse_todotxt_line("(A) 2024-05-01 Call Anna +work +q3 @phone due:2024-05-10 id:7")
+                .unwrap()
+                .unwrap();
+        assert_eq!(task.title, "Call Anna id:7");
+        assert_eq!(task.priority, Priority::Urgent);
+        assert_eq!(task.board.as_deref(), Some("work"));
+        assert_eq!(task.tags, ["q3", "phone"]);
+        assert_eq!(task.created_at, parse_todotxt_date("2024-05-01").unwrap());
+        assert_eq!(task.due_at, parse_todotxt_date("2024-05-10").unwrap());
+        assert!(!task.done);
+
+        let task = parse_todotxt_line("x (D) 2024-05-02 2024-05-01 pay rent")
+            .unwrap()
+            .unwrap();
+        assert!(task.done);
+        assert_eq!(task.priority, Priority::Low);
+        assert_eq!(task.created_at, parse_todotxt_date("2024-05-01").unwrap());
+        assert_eq!(task.completed_at, parse_todotxt_date("2024-05-02").unwrap());
+        assert_eq!(task.title, "pay rent");
+
+        assert_eq!(parse_todotxt_line("   ").unwrap(), None);
+        assert!(parse_todotxt_line("2024-02-30 call Anna").is_err());
+        assert!(parse_todotxt_line("call Anna due:2024-13-01").is_err());
+        assert!(parse_todotxt_line("+work @home").is_err());
+        assert!(parse_todotxt_line("call due:soon").is_err());
+    }
+
+    #[test]
+    fn column_mappings() {
+        let mapping: ColumnMapping = "title=Summary, due = Deadline".parse().unwrap();
+        assert_eq!(mapping.header(Field::Title), "Summary");
+        assert_eq!(mapping.header(Field::Due), "Deadline");
+        assert_eq!(mapping.header(Field::Created), "created_at");
+        assert!("name=Summary".parse::<ColumnMapping>().is_err());
+        assert!("title".parse::<ColumnMapping>().is_err());
+    }
+}
@@ -0,0 +1,89 @@
+use crate::{
+    dates::format_datetime, TABLE_BOARDS, TABLE_COLUMNS, TABLE_COMMENTS, TABLE_DEPENDENCIES,
+    TABLE_JOURNAL, TABLE_REMINDERS, TABLE_TAGS, TABLE_TASKS, TABLE_TASK_TAGS,
+};
+use chrono::{DateTime, Utc};
+use rusqlite::Connection;
+use tabled::Tabled;
+
+///Operations kept for undo, older ones are forgotten
+pub const JOURNAL_LIMIT: u32 = 100;
+///Operations listed by the journal command
+pub const DEFAULT_OPERATIONS_LISTED: u32 = 10;
+///Tables whose changes can be undone. The history, the search index and the journal itself
+///are left out
+pub const JOURNALED_TABLES: [&str; 8] = [
+    TABLE_BOARDS,
+    TABLE_COLUMNS,
+    TABLE_TASKS,
+    TABLE_COMMENTS,
+    TABLE_REMINDERS,
+    TABLE_DEPENDENCIES,
+    TABLE_TAGS,
+    TABLE_TASK_TAGS,
+];
+///Temporary table holding the operation being recorded, empty otherwise
+pub(crate) const TABLE_RECORDING: &str = "journal_recording";
+
+fn display_datetime(datetime: &DateTime<Utc>) -> String {
+    format_datetime(datetime)
+}
+
+fn display_undone(undone: &bool) -> String {
+    match undone {
+        true => "undone".to_string(),
+        false => String::new(),
+    }
+}
+
+///User action whose changes can be undone and redone as a whole
+#[derive(Debug, Clone, Tabled)]
+pub struct Operation {
+    ///Grows past the u16 ids of tasks, the journal being trimmed but never renumbered
+    pub id: i64,
+    pub description: String,
+    #[tabled(rename = "at", display_with = "display_datetime")]
+    pub created_at: DateTime<Utc>,
+    #[tabled(rename = "status", display_with = "display_undone")]
+    pub undone: bool,
+}
+
+///Temporary triggers writing the inverse statement of every change of the journaled tables
+///while an operation is recorded. Created on each connection from the current columns, so
+///migrations don't have to maintain them
+pub(crate) fn create_triggers(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(&format!(
+        "CREATE TEMP TABLE IF NOT EXISTS {TABLE_RECORDING} (operation_id INTEGER NOT NULL);"
+    ))?;
+
+    for table in JOURNALED_TABLES {
+        let mut stmt = conn.prepare(&format!("PRAGMA main.table_info({table})"))?;
+        let columns = stmt
+            .query_map([], |row| row.get(1))?
+            .collect::<rusqlite::Result<Vec<String>>>()?;
+        let names = columns.join(", ");
+        let values: Vec<String> = columns.iter().map(|x| format!("quote(old.{x})")).collect();
+        let assignments: Vec<String> = columns
+            .iter()
+            .map(|x| format!("'{x} = ' || quote(old.{x})"))
+            .collect();
+        let recording = format!("WHEN EXISTS (SELECT 1 FROM {TABLE_RECORDING})");
+        let journal =
+            format!("INSERT INTO {TABLE_JOURNAL} (operation_id, statement) SELECT operation_id,");
+
+        conn.execute_batch(&format!(
+            "CREATE TEMP TRIGGER IF NOT EXISTS {table}_journal_insert AFTER INSERT ON main.{table} {recording} BEGIN
+                {journal} 'DELETE FROM {table} WHERE rowid = ' || new.rowid FROM {TABLE_RECORDING};
+            END;
+            CREATE TEMP TRIGGER IF NOT EXISTS {table}_journal_update AFTER UPDATE ON main.{table} {recording} BEGIN
+                {journal} 'UPDATE {table} SET ' || {} || ' WHERE rowid = ' || old.rowid FROM {TABLE_RECORDING};
+            END;
+            CREATE TEMP TRIGGER IF NOT EXISTS {table}_journal_delete AFTER DELETE ON main.{table} {recording} BEGIN
+                {journal} 'INSERT INTO {table} (rowid, {names}) VALUES (' || old.rowid || ', ' || {} || ')' FROM {TABLE_RECORDING};
+            END;",
+            assignments.join(" || ', ' || "),
+            values.join(" || ', ' || "),
+        ))?;
+    }
+    Ok(())
+}
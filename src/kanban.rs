@@ -0,0 +1,351 @@
+use crate::{
+    dates::{format_datetime, format_relative},
+    error::Result,
+    set_task_column, set_task_done,
+    store::{TaskFilter, TaskSort, TaskStore},
+    Board, Column, Comment, Priority, Task,
+};
+use chrono::Utc;
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyEventKind},
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Clear, List, ListItem, ListState, Paragraph, Wrap},
+    DefaultTerminal, Frame,
+};
+
+///Keys shown at the bottom of the view
+const KEY_HELP: &str =
+    "←/→ column  ↑/↓ task  </> move task  d done/undone  c comments  r reload  q quit";
+
+///Tasks of a board laid out by column, with the selected task and the outcome of the last action
+pub struct Kanban {
+    pub board: Board,
+    pub columns: Vec<Column>,
+    ///Tasks of each column, in the order of `columns`
+    pub tasks: Vec<Vec<Task>>,
+    pub column: usize,
+    pub row: usize,
+    ///Comments of the selected task, shown over the board when open
+    pub comments: Option<Vec<Comment>>,
+    pub status: String,
+}
+
+impl Kanban {
+    pub fn load(store: &dyn TaskStore, board_id: u16) -> Result<Kanban> {
+        let mut kanban = Kanban {
+            board: store.get_board(board_id)?,
+            columns: Vec::new(),
+            tasks: Vec::new(),
+            column: 0,
+            row: 0,
+            comments: None,
+            status: String::new(),
+        };
+        kanban.reload(store)?;
+        Ok(kanban)
+    }
+
+    ///Read the columns and tasks again, keeping the selection within bounds
+    pub fn reload(&mut self, store: &dyn TaskStore) -> Result<()> {
+        self.columns = store.list_columns(self.board.id)?;
+        let filter = TaskFilter {
+            sort: TaskSort::Priority,
+            ..TaskFilter::default().board(self.board.id)
+        };
+        let tasks = store.list_tasks(&filter)?;
+        self.tasks = self
+            .columns
+            .iter()
+            .map(|column| {
+                tasks
+                    .iter()
+                    .filter(|x| x.column_id == Some(column.id))
+                    .cloned()
+                    .collect()
+            })
+            .collect();
+        self.column = self.column.min(self.columns.len().saturating_sub(1));
+        self.row = self.row.min(self.column_len().saturating_sub(1));
+        Ok(())
+    }
+
+    pub fn selected(&self) -> Option<&Task> {
+        self.tasks.get(self.column)?.get(self.row)
+    }
+
+    fn column_len(&self) -> usize {
+        self.tasks.get(self.column).map_or(0, Vec::len)
+    }
+
+    ///Select the task wherever it is now
+    fn follow(&mut self, task_id: u16) {
+        for (column, tasks) in self.tasks.iter().enumerate() {
+            if let Some(row) = tasks.iter().position(|x| x.id == task_id) {
+                self.column = column;
+                self.row = row;
+            }
+        }
+    }
+
+    fn select_column(&mut self, offset: isize) {
+        let last = self.columns.len().saturating_sub(1) as isize;
+        self.column = (self.column as isize + offset).clamp(0, last) as usize;
+        self.row = self.row.min(self.column_len().saturating_sub(1));
+    }
+
+    fn select_row(&mut self, offset: isize) {
+        let last = self.column_len().saturating_sub(1) as isize;
+        self.row = (self.row as isize + offset).clamp(0, last) as usize;
+    }
+
+    ///Apply a key. Returns false when the view should close
+    pub fn handle_key(&mut self, store: &dyn TaskStore, key: KeyCode) -> Result<bool> {
+        if self.comments.is_some() {
+            if matches!(
+                key,
+                KeyCode::Esc | KeyCode::Enter | KeyCode::Char('c') | KeyCode::Char('q')
+            ) {
+                self.comments = None;
+            }
+            return Ok(true);
+        }
+
+        match key {
+            KeyCode::Esc | KeyCode::Char('q') => return Ok(false),
+            KeyCode::Left | KeyCode::Char('h') => self.select_column(-1),
+            KeyCode::Right | KeyCode::Char('l') => self.select_column(1),
+            KeyCode::Up | KeyCode::Char('k') => self.select_row(-1),
+            KeyCode::Down | KeyCode::Char('j') => self.select_row(1),
+            KeyCode::Char('<') | KeyCode::Char('H') => self.move_selected(store, -1)?,
+            KeyCode::Char('>') | KeyCode::Char('L') => self.move_selected(store, 1)?,
+            KeyCode::Char('d') | KeyCode::Char(' ') => self.toggle_selected(store)?,
+            KeyCode::Enter | KeyCode::Char('c') => self.open_comments(store)?,
+            KeyCode::Char('r') => {
+                self.reload(store)?;
+                self.status = "Reloaded".to_string();
+            }
+            _ => (),
+        }
+        Ok(true)
+    }
+
+    ///Move the selected task to the previous or next column
+    fn move_selected(&mut self, store: &dyn TaskStore, offset: isize) -> Result<()> {
+        let task_id = match self.selected() {
+            Some(task) => task.id,
+            None => return Ok(()),
+        };
+        let target = usize::try_from(self.column as isize + offset).ok();
+        let column = match target.and_then(|x| self.columns.get(x)) {
+            Some(column) => column.clone(),
+            None => return Ok(()),
+        };
+
+        //Each move is undone on its own
+        let mut next = None;
+        let description = format!("Move task {} to {}", task_id, column.title);
+        store.journaled(&description, &mut || {
+            next = set_task_column(store, task_id, column.id)?;
+            Ok(())
+        })?;
+        self.reload(store)?;
+        self.follow(task_id);
+        self.status = match next {
+            Some(next) => format!(
+                "Task {} moved to {}, next occurrence is task {}",
+                task_id, column.title, next
+            ),
+            None => format!("Task {} moved to {}", task_id, column.title),
+        };
+        Ok(())
+    }
+
+    fn toggle_selected(&mut self, store: &dyn TaskStore) -> Result<()> {
+        let (task_id, done) = match self.selected() {
+            Some(task) => (task.id, task.done == 0),
+            None => return Ok(()),
+        };
+
+        let mut next = None;
+        let description = match done {
+            true => format!("Complete task {}", task_id),
+            false => format!("Reopen task {}", task_id),
+        };
+        store.journaled(&description, &mut || {
+            next = set_task_done(store, task_id, done)?;
+            Ok(())
+        })?;
+        self.reload(store)?;
+        self.follow(task_id);
+        self.status = match (done, next) {
+            (true, Some(next)) => {
+                format!("Task {} done, next occurrence is task {}", task_id, next)
+            }
+            (true, None) => format!("Task {} done", task_id),
+            (false, _) => format!("Task {} reopened", task_id),
+        };
+        Ok(())
+    }
+
+    fn open_comments(&mut self, store: &dyn TaskStore) -> Result<()> {
+        if let Some(task) = self.selected() {
+            self.comments = Some(store.list_comments(task.id)?);
+        }
+        Ok(())
+    }
+
+    pub fn render(&self, frame: &mut Frame) {
+        let [title, board, status] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Min(3),
+            Constraint::Length(2),
+        ])
+        .areas(frame.area());
+
+        frame.render_widget(
+            Paragraph::new(format!("{} - {}", self.board.id, self.board.title))
+                .style(Style::new().add_modifier(Modifier::BOLD)),
+            title,
+        );
+
+        let constraints =
+            vec![Constraint::Ratio(1, self.columns.len().max(1) as u32); self.columns.len()];
+        let areas = Layout::horizontal(constraints).split(board);
+        for (index, column) in self.columns.iter().enumerate() {
+            self.render_column(frame, areas[index], index, column);
+        }
+
+        frame.render_widget(
+            Paragraph::new(vec![
+                Line::from(self.status.as_str()),
+                Line::styled(KEY_HELP, Style::new().fg(Color::DarkGray)),
+            ]),
+            status,
+        );
+
+        if let Some(comments) = &self.comments {
+            self.render_comments(frame, comments);
+        }
+    }
+
+    fn render_column(&self, frame: &mut Frame, area: Rect, index: usize, column: &Column) {
+        let tasks = &self.tasks[index];
+        let active = index == self.column;
+        let border = match active {
+            true => Style::new().fg(Color::Yellow),
+            false => Style::new(),
+        };
+        let marker = match column.terminal {
+            true => " ✓",
+            false => "",
+        };
+        let block = Block::bordered()
+            .title(format!("{} ({}){}", column.title, tasks.len(), marker))
+            .border_style(border);
+
+        let items: Vec<ListItem> = tasks.iter().map(task_item).collect();
+        let list = List::new(items)
+            .block(block)
+            .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+        let mut state = ListState::default().with_selected(match active && !tasks.is_empty() {
+            true => Some(self.row),
+            false => None,
+        });
+        frame.render_stateful_widget(list, area, &mut state);
+    }
+
+    fn render_comments(&self, frame: &mut Frame, comments: &[Comment]) {
+        let area = frame.area();
+        let popup = Rect {
+            x: area.width / 6,
+            y: area.height / 4,
+            width: area.width * 2 / 3,
+            height: area.height / 2,
+        };
+        let lines: Vec<Line> = match comments.is_empty() {
+            true => vec![Line::from("No comments for this Task")],
+            false => comments
+                .iter()
+                .map(|x| Line::from(format!("{} [{}]", x.title, format_datetime(&x.created_at))))
+                .collect(),
+        };
+        let title = self
+            .selected()
+            .map(|x| format!("Comments of {}", x.title))
+            .unwrap_or_default();
+
+        frame.render_widget(Clear, popup);
+        frame.render_widget(
+            Paragraph::new(lines)
+                .wrap(Wrap { trim: true })
+                .block(Block::bordered().title(title)),
+            popup,
+        );
+    }
+}
+
+fn priority_color(priority: Priority) -> Color {
+    match priority {
+        Priority::Low => Color::DarkGray,
+        Priority::Normal => Color::Reset,
+        Priority::High => Color::Yellow,
+        Priority::Urgent => Color::Red,
+    }
+}
+
+///"3 report", followed by the due date, blockers and subtask progress when set
+fn task_item(task: &Task) -> ListItem<'static> {
+    let mut spans = vec![Span::styled(
+        format!("{} {}", task.id, task.title),
+        Style::new().fg(priority_color(task.priority)),
+    )];
+    if let Some(due_at) = &task.due_at {
+        let now = Utc::now();
+        let style = match *due_at < now && task.done == 0 {
+            true => Style::new().fg(Color::Red),
+            false => Style::new().fg(Color::DarkGray),
+        };
+        spans.push(Span::styled(
+            format!(" {}", format_relative(due_at, &now)),
+            style,
+        ));
+    }
+    if task.blockers > 0 {
+        spans.push(Span::styled(" blocked", Style::new().fg(Color::Red)));
+    }
+    if task.subtasks.total > 0 {
+        spans.push(Span::raw(format!(" {}", task.subtasks)));
+    }
+    ListItem::new(Line::from(spans))
+}
+
+///Full screen view of a board until the user quits
+pub fn run(store: &dyn TaskStore, board_id: u16) -> Result<()> {
+    let mut kanban = Kanban::load(store, board_id)?;
+    let mut terminal = ratatui::try_init()?;
+    let result = event_loop(&mut terminal, store, &mut kanban);
+    ratatui::try_restore()?;
+    result
+}
+
+fn event_loop(
+    terminal: &mut DefaultTerminal,
+    store: &dyn TaskStore,
+    kanban: &mut Kanban,
+) -> Result<()> {
+    loop {
+        terminal.draw(|frame| kanban.render(frame))?;
+        let key = match event::read()? {
+            Event::Key(key) if key.kind == KeyEventKind::Press => key,
+            _ => continue,
+        };
+        match kanban.handle_key(store, key.code) {
+            Ok(true) => (),
+            Ok(false) => return Ok(()),
+            Err(error) if error.is_fatal() => return Err(error),
+            Err(error) => kanban.status = error.to_string(),
+        }
+    }
+}
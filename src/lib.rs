@@ -1,57 +1,302 @@
-// use home::home_dir;
-use crate::user_input::{get_user_confirmation, get_user_selection};
-use chrono::{TimeZone, Utc};
+use crate::user_input::{
+    get_user_confirmation, get_user_date, get_user_input, get_user_selection,
+    get_user_selection_index,
+};
+use chrono::{DateTime, Duration, Local, Utc};
+use clap::ValueEnum;
 use colored::*;
-use rusqlite::{Connection, Result};
-use tabled::Tabled;
+use config::{DatabaseLocation, DatabaseSource};
+use dao::SqliteStore;
+use dates::{
+    format_datetime, format_duration, format_relative, parse_reminder_time, ReminderTime,
+    REMINDER_EXAMPLES,
+};
+use error::{Error, Result};
+use recurrence::{Recurrence, NO_RECURRENCE, RECURRENCE_EXAMPLES};
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, ValueRef};
+use serde::{Deserialize, Serialize};
+use std::{fmt, path::PathBuf, sync::OnceLock};
+use store::{NewTask, TaskFilter, TaskSort, TaskStore};
+use tabled::{Disable, Style, Table, Tabled};
+use tags::{validate_tag_name, TAG_EXAMPLES};
 
+pub mod cli;
+pub mod config;
 pub mod dao;
+pub mod dates;
+pub mod error;
+pub mod export;
+pub mod filter;
+pub mod history;
+pub mod import;
+pub mod journal;
+pub mod kanban;
+pub mod migrations;
+pub mod notify;
+pub mod recurrence;
+pub mod report;
+pub mod search;
+pub mod store;
+pub mod tags;
 pub mod user_input;
 pub const TABLE_TASKS: &str = "tasks";
 pub const TABLE_BOARDS: &str = "boards";
 pub const TABLE_COMMENTS: &str = "comments";
+pub const TABLE_TASKS_SEARCH: &str = "tasks_fts";
+pub const TABLE_COMMENTS_SEARCH: &str = "comments_fts";
+pub const TABLE_REMINDERS: &str = "reminders";
+pub const TABLE_DEPENDENCIES: &str = "dependencies";
+pub const TABLE_TAGS: &str = "tags";
+pub const TABLE_TASK_TAGS: &str = "task_tags";
+pub const TABLE_COLUMNS: &str = "board_columns";
+pub const TABLE_HISTORY: &str = "history";
+pub const TABLE_OPERATIONS: &str = "operations";
+pub const TABLE_JOURNAL: &str = "journal";
 pub const DELETE: &str = "Delete";
 pub const CHANGE: &str = "Change";
 pub const ADD_COMMENT: &str = "Add comment";
 pub const CREATE_TASK: &str = "Create Task";
 pub const VIEW_PENDING_TASKS: &str = "View Tasks [Pending]";
 pub const VIEW_DONE_TASKS: &str = "View Tasks [Done]";
+pub const VIEW_READY_TASKS: &str = "View Tasks [Ready]";
 pub const CREATE_BOARD: &str = "Create Board";
 pub const VIEW_BOARDS: &str = "View Boards";
+pub const SEARCH_TASKS: &str = "Search Tasks";
+pub const UNDO: &str = "Undo";
+pub const REDO: &str = "Redo";
 pub const EXIT: &str = "Exit";
 
-pub const MAIN_MENU_OPTIONS: [&str; 6] = [
+pub const MAIN_MENU_OPTIONS: [&str; 10] = [
     CREATE_TASK,
     VIEW_PENDING_TASKS,
     VIEW_DONE_TASKS,
+    VIEW_READY_TASKS,
+    SEARCH_TASKS,
     CREATE_BOARD,
     VIEW_BOARDS,
+    UNDO,
+    REDO,
     EXIT,
 ];
 
-const TASK_ACTIONS: [&str; 6] = [
+const TASK_ACTIONS: [&str; 16] = [
     DELETE,
     CHANGE,
     "Add comment",
     "View comments",
-    "Set reminder",
+    "Set due date",
+    "Add reminder",
+    "Change title",
+    "Move to board",
+    "Set priority",
+    "Set recurrence",
+    "Add subtask",
+    "Add blocker",
+    "Edit tags",
+    "Move to column",
+    "View history",
     "Cancel",
 ];
-pub const BOARD_ACTIONS: [&str; 3] = ["Delete", "Change title", "Cancel"];
+///Columns of the task table left out of the overdue listing
+const OVERDUE_HIDDEN_COLUMNS: [&str; 2] = ["done", "board_id"];
+pub const BOARD_ACTIONS: [&str; 7] = [
+    "Delete",
+    "Change title",
+    "Move pending tasks",
+    "View columns",
+    "Add column",
+    "Open kanban view",
+    "Cancel",
+];
+///Columns of a new board, title and whether it is the terminal one
+pub const DEFAULT_COLUMNS: [(&str, bool); 3] = [("Todo", false), ("Doing", false), ("Done", true)];
 pub const SAMPLE_TITLE: &str = "sample";
+///Datetimes are shown in local time with this format
 pub const DATETIME_FORMAT: &str = "%a, %b %e %Y %T";
 pub const DATE_FORMAT: &str = "%Y%m%d";
-pub const TIME_FORMAT: &str = "%H:%M:%S";
-pub const ALTERNATIVE_DATETIME_FORMAT: &str = "%Y%m%d %H:%M:%S";
 
-#[derive(Debug, Tabled)]
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    Low,
+    #[default]
+    Normal,
+    High,
+    Urgent,
+}
+
+pub const PRIORITIES: [Priority; 4] = [
+    Priority::Low,
+    Priority::Normal,
+    Priority::High,
+    Priority::Urgent,
+];
+
+impl Priority {
+    pub fn colored(&self) -> ColoredString {
+        let name = self.to_string();
+        match self {
+            Priority::Low => name.dimmed(),
+            Priority::Normal => name.normal(),
+            Priority::High => name.yellow(),
+            Priority::Urgent => name.red().bold(),
+        }
+    }
+}
+
+impl fmt::Display for Priority {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Priority::Low => "low",
+            Priority::Normal => "normal",
+            Priority::High => "high",
+            Priority::Urgent => "urgent",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl ToSql for Priority {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(*self as i64))
+    }
+}
+
+impl FromSql for Priority {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        let index = value.as_i64()?;
+        PRIORITIES
+            .get(index as usize)
+            .copied()
+            .ok_or(FromSqlError::OutOfRange(index))
+    }
+}
+
+fn display_priority(priority: &Priority) -> String {
+    priority.colored().to_string()
+}
+
+fn display_datetime(datetime: &DateTime<Utc>) -> String {
+    format_datetime(datetime)
+}
+
+///"in 3h", in red once past
+pub(crate) fn display_due(due_at: &Option<DateTime<Utc>>) -> String {
+    let now = Utc::now();
+    match due_at {
+        Some(due_at) if *due_at < now => format_relative(due_at, &now).red().to_string(),
+        Some(due_at) => format_relative(due_at, &now),
+        None => String::new(),
+    }
+}
+
+fn display_before_due(before_due: &Option<Duration>) -> String {
+    match before_due {
+        Some(before_due) if before_due.is_zero() => "at due".to_string(),
+        Some(before_due) => format_duration(*before_due),
+        None => String::new(),
+    }
+}
+
+fn display_fired(fired_at: &Option<DateTime<Utc>>) -> String {
+    fired_at.as_ref().map(format_datetime).unwrap_or_default()
+}
+
+fn display_recurrence(recurrence: &Option<Recurrence>) -> String {
+    recurrence
+        .as_ref()
+        .map(Recurrence::describe)
+        .unwrap_or_default()
+}
+
+///Done and total subtasks of a task, shown as "3/5"
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Progress {
+    pub done: u16,
+    pub total: u16,
+}
+
+impl fmt::Display for Progress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.done, self.total)
+    }
+}
+
+fn display_progress(progress: &Progress) -> String {
+    match progress.total {
+        0 => String::new(),
+        _ => progress.to_string(),
+    }
+}
+
+fn display_blockers(blockers: &u16) -> String {
+    match blockers {
+        0 => String::new(),
+        _ => format!("by {}", blockers).red().to_string(),
+    }
+}
+
+fn display_tags(tags: &[String]) -> String {
+    tags.join(", ")
+}
+
+fn display_parent(parent_id: &Option<u16>) -> String {
+    parent_id.map(|x| x.to_string()).unwrap_or_default()
+}
+
+#[derive(Debug, Clone, Tabled)]
 pub struct Task {
     pub id: u16,
     pub title: String,
+    #[tabled(display_with = "display_priority")]
+    pub priority: Priority,
+    ///Derived from the column, 1 in the terminal column of the board
     pub done: u8,
     pub board_id: u16,
-    pub created_at: String,
-    pub reminder: String,
+    ///None only for tasks whose board is missing
+    #[tabled(skip)]
+    pub column_id: Option<u16>,
+    ///Read only, title of the column
+    #[tabled(rename = "status")]
+    pub column: String,
+    #[tabled(display_with = "display_datetime")]
+    pub created_at: DateTime<Utc>,
+    ///Read only, set when the task enters the terminal column and cleared when it leaves
+    #[tabled(skip)]
+    pub completed_at: Option<DateTime<Utc>>,
+    #[tabled(rename = "due", display_with = "display_due")]
+    pub due_at: Option<DateTime<Utc>>,
+    #[tabled(rename = "repeats", display_with = "display_recurrence")]
+    pub recurrence: Option<Recurrence>,
+    #[tabled(rename = "parent", display_with = "display_parent")]
+    pub parent_id: Option<u16>,
+    ///Read only, counted from the subtasks
+    #[tabled(rename = "subtasks", display_with = "display_progress")]
+    pub subtasks: Progress,
+    ///Read only, number of pending tasks blocking this one
+    #[tabled(rename = "blocked", display_with = "display_blockers")]
+    pub blockers: u16,
+    ///Sorted by name
+    #[tabled(display_with = "display_tags")]
+    pub tags: Vec<String>,
+}
+
+///Notification of a task, at a date or some time before its due date
+#[derive(Debug, Clone, Tabled)]
+pub struct Reminder {
+    pub id: u16,
+    #[tabled(skip)]
+    pub task_id: u16,
+    #[tabled(rename = "remind at", display_with = "display_datetime")]
+    pub remind_at: DateTime<Utc>,
+    ///Set when the reminder follows the due date of its task
+    #[tabled(rename = "before due", display_with = "display_before_due")]
+    pub before_due: Option<Duration>,
+    ///When the watch mode notified it
+    #[tabled(rename = "fired", display_with = "display_fired")]
+    pub fired_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug)]
@@ -60,11 +305,25 @@ pub struct Board {
     pub title: String,
 }
 
+///Ordered status of the tasks of a board. Each board has exactly one terminal column,
+///tasks in it being done, and at least one other
+#[derive(Debug, Clone, Tabled)]
+pub struct Column {
+    pub id: u16,
+    #[tabled(skip)]
+    pub board_id: u16,
+    pub title: String,
+    pub position: u16,
+    pub terminal: bool,
+    ///Read only, number of tasks in the column
+    pub tasks: u16,
+}
+
 #[derive(Debug)]
 pub struct Comment {
     pub id: u16,
     pub title: String,
-    pub created_at: String,
+    pub created_at: DateTime<Utc>,
 }
 
 #[derive(Debug)]
@@ -78,201 +337,773 @@ pub enum Color {
     White,
 }
 
-#[derive(Debug)]
-pub struct Record {
-    pub qtd: u16,
+///Row whose parent no longer exists
+#[derive(Debug, Tabled)]
+pub struct Orphan {
+    pub table: String,
+    pub id: i64,
+    pub parent: String,
 }
 
-pub fn get_connection() -> Connection {
-    let conn = Connection::open(&get_database_path()).unwrap();
-    conn
+///Open the store at the resolved database location
+pub fn open_store() -> Result<SqliteStore> {
+    SqliteStore::open(get_database_path())
 }
 
-// pub fn get_database_path() -> String {
-//     format!(
-//         "{}/{}.db3",
-//         home_dir().unwrap().display(),
-//         env!("CARGO_PKG_NAME")
-//     )
-// }
+static DATABASE_LOCATION: OnceLock<DatabaseLocation> = OnceLock::new();
 
-pub fn get_database_path() -> String {
-    format!("{}.db3", env!("CARGO_PKG_NAME"))
+///Set once at startup, before the store is opened
+pub fn set_database_location(location: DatabaseLocation) {
+    let _ = DATABASE_LOCATION.set(location);
+}
+
+pub fn get_database_location() -> &'static DatabaseLocation {
+    DATABASE_LOCATION.get_or_init(|| DatabaseLocation {
+        path: config::get_default_database_path(),
+        source: DatabaseSource::Default,
+    })
+}
+
+pub fn get_database_path() -> PathBuf {
+    get_database_location().path.clone()
+}
+
+///Id at the start of a "id - title" selection
+pub fn selected_id(selection: &str) -> Result<u16> {
+    selection
+        .split_whitespace()
+        .next()
+        .and_then(|x| x.parse().ok())
+        .ok_or_else(|| Error::Validation(format!("Invalid selection {}", selection)))
 }
 
 ///title, id
-fn select_comment(comments_raw: &Vec<Comment>, task_title: &str) -> Option<(String, u16)> {
+fn select_comment(comments_raw: &[Comment], task_title: &str) -> Result<(String, u16)> {
     let mut comments: Vec<String> = comments_raw
         .iter()
-        .map(|x| format!("{} - {} [{}]", &x.id, &x.title, &x.created_at))
+        .map(|x| {
+            format!(
+                "{} - {} [{}]",
+                &x.id,
+                &x.title,
+                format_datetime(&x.created_at)
+            )
+        })
         .collect();
     comments.sort();
 
-    let selected_comment = user_input::get_user_selection(
+    let (selected_comment, _) = user_input::get_user_selection(
         &comments,
         format!("Comments for task {}", task_title).as_str(),
-    );
-    let selected_comment_id: u16 = selected_comment
-        .0
-        .split_whitespace()
-        .next()
-        .unwrap()
-        .parse()
-        .unwrap();
+    )?;
+    let selected_comment_id = selected_id(&selected_comment)?;
 
     let selected_comment: &Comment = comments_raw
         .iter()
         .find(|x| x.id == selected_comment_id)
-        .unwrap();
+        .ok_or_else(|| Error::NotFound(format!("Comment {}", selected_comment_id)))?;
 
     let selected_comment = (selected_comment.title.to_string(), selected_comment.id);
-    Some(selected_comment)
+    Ok(selected_comment)
 }
 
 ///title, id
-pub fn select_board() -> Option<(String, u16)> {
-    let records_qtd = dao::get_records_qtd(TABLE_BOARDS).unwrap();
+pub fn select_board(store: &dyn TaskStore) -> Result<Option<(String, u16)>> {
+    let boards_raw = store.list_boards()?;
 
-    if records_qtd == 0 {
+    if boards_raw.is_empty() {
         display_message("info", "No Boards found in database", Color::Blue);
-        return None;
+        return Ok(None);
     }
 
-    let boards_raw = dao::get_boards().unwrap();
-
     let mut boards: Vec<String> = boards_raw
         .iter()
         .map(|x| format!("{} - {}", &x.id, &x.title))
         .collect();
     boards.sort();
 
-    let selected_board = user_input::get_user_selection(&boards, "Board");
-    let selected_board_id: u16 = selected_board
-        .0
-        .split_whitespace()
-        .next()
-        .unwrap()
-        .parse()
-        .unwrap();
+    let (selected_board, _) = user_input::get_user_selection(&boards, "Board")?;
+    let selected_board_id = selected_id(&selected_board)?;
 
     let selected_board: &Board = boards_raw
         .iter()
         .find(|x| x.id == selected_board_id)
-        .unwrap();
+        .ok_or_else(|| Error::NotFound(format!("Board {}", selected_board_id)))?;
 
     let selected_board = (selected_board.title.to_string(), selected_board.id);
-    Some(selected_board)
+    Ok(Some(selected_board))
 }
 
-pub fn list_boards() -> Result<()> {
-    let selected_board = select_board();
-    if selected_board.is_none() {
-        return Ok(());
+///title, id
+pub fn select_task(store: &dyn TaskStore, done: u8) -> Result<Option<(String, u16)>> {
+    let records_qtd = store.count_records(TABLE_TASKS)?;
+    if records_qtd == 0 {
+        display_message("info", "No Tasks found in database", Color::Cyan);
+        return Ok(None);
+    }
+    let mut filter = TaskFilter {
+        sort: TaskSort::Priority,
+        ..TaskFilter::done(done == 1)
+    };
+
+    let all_boards = get_user_confirmation("From all boards")?;
+
+    if !all_boards {
+        let (_, board_id) = match select_board(store)? {
+            Some(board) => board,
+            None => return Ok(None),
+        };
+        filter.board_ids.push(board_id);
+    }
+
+    let tags = get_user_input(
+        &format!("Tags (e.g. {}, empty for any)", TAG_EXAMPLES),
+        "",
+        true,
+    )?;
+    filter.tags = tags.parse()?;
+
+    let tasks_raw = store.list_tasks(&filter)?;
+    if tasks_raw.is_empty() {
+        display_message(
+            "info",
+            "No Tasks found in database with these criteria",
+            Color::Cyan,
+        );
+        return Ok(None);
     }
-    let (board_title, board_id) = selected_board.unwrap();
 
-    let (_, action_index) = get_user_selection(
-        &BOARD_ACTIONS.to_vec(),
+    let tasks: Vec<String> = tasks_raw
+        .iter()
+        .map(|x| format!("{} - {} [{}]", &x.id, &x.title, x.priority.colored()))
+        .collect();
+
+    let (selected_task, _) = get_user_selection(&tasks, "Task")?;
+    let task_id = selected_id(&selected_task)?;
+
+    let selected_task = tasks_raw
+        .iter()
+        .find(|x| x.id == task_id)
+        .ok_or_else(|| Error::NotFound(format!("Task {}", task_id)))?;
+    let selected_task = (selected_task.title.to_string(), selected_task.id);
+    Ok(Some(selected_task))
+}
+
+pub fn list_boards(store: &dyn TaskStore) -> Result<()> {
+    let Some((board_title, board_id)) = select_board(store)? else {
+        return Ok(());
+    };
+
+    let (action, action_index) = get_user_selection(
+        &BOARD_ACTIONS,
         format!("Action on Board {}", board_title).as_str(),
-    );
+    )?;
 
+    let description = format!("{}: board {} {}", action, board_id, board_title);
+    let journaled = |f: &mut dyn FnMut() -> Result<()>| store.journaled(&description, f);
     match action_index {
-        0 => delete_board(&board_title, board_id)?,
-        1 => dao::edit_board(&board_title, board_id)?,
-        _ => return Ok(()),
+        0 => journaled(&mut || delete_board(store, &board_title, board_id)),
+        1 => journaled(&mut || edit_board(store, &board_title, board_id)),
+        2 => journaled(&mut || move_pending_tasks(store, &board_title, board_id)),
+        3 => list_columns(store, board_id),
+        4 => journaled(&mut || create_column(store, board_id)),
+        //Journals each move
+        5 => kanban::run(store, board_id),
+        _ => Ok(()),
+    }
+}
+
+pub fn create_board(store: &dyn TaskStore) -> Result<()> {
+    let title = get_user_input("Board title", SAMPLE_TITLE, true)?;
+    store.journaled(&format!("Create board {}", title), &mut || {
+        store.add_board(&title).map(|_| ())
+    })
+}
+
+pub fn edit_board(store: &dyn TaskStore, title: &str, id: u16) -> Result<()> {
+    let title = get_user_input("New Board title", title, true)?;
+    store.rename_board(id, &title)
+}
+
+pub fn list_columns(store: &dyn TaskStore, board_id: u16) -> Result<()> {
+    let columns = store.list_columns(board_id)?;
+    println!("{}", Table::new(columns).with(Style::modern()));
+    Ok(())
+}
+
+///Added before the terminal column
+pub fn create_column(store: &dyn TaskStore, board_id: u16) -> Result<()> {
+    let title = get_user_input("Column title", SAMPLE_TITLE, true)?;
+    store.add_column(board_id, &title)?;
+    Ok(())
+}
+
+///Move a task to a column of its board. Entering or leaving the terminal column completes
+///or reopens it, see `set_task_done`. Returns the id of the next occurrence of a recurring task
+pub fn set_task_column(store: &dyn TaskStore, task_id: u16, column_id: u16) -> Result<Option<u16>> {
+    let task = store.get_task(task_id)?;
+    let column = store.get_column(column_id)?;
+    if column.board_id != task.board_id {
+        return Err(Error::Validation(format!(
+            "Column {} is not on the board of task {}",
+            column.title, task_id
+        )));
+    }
+    let next = match column.terminal == (task.done == 1) {
+        true => None,
+        false => set_task_done(store, task_id, column.terminal)?,
     };
 
+    let mut task = store.get_task(task_id)?;
+    task.column_id = Some(column_id);
+    store.update_task(&task)?;
+    Ok(next)
+}
+
+pub fn select_column(store: &dyn TaskStore, task_id: u16) -> Result<()> {
+    let task = store.get_task(task_id)?;
+    let columns = store.list_columns(task.board_id)?;
+    let titles: Vec<&str> = columns.iter().map(|x| x.title.as_str()).collect();
+    let current = columns
+        .iter()
+        .position(|x| Some(x.id) == task.column_id)
+        .unwrap_or_default();
+    let selection = get_user_selection_index(&titles, "Column", current)?;
+    if let Some(next) = set_task_column(store, task_id, columns[selection].id)? {
+        display_next_occurrence(store, next)?;
+    }
     Ok(())
 }
 
-fn delete_board(board_title: &str, board_id: u16) -> Result<()> {
-    let deletion_confirmation =
-        get_user_confirmation(format!("Are you sure you want to delete {}", &board_title).as_str());
+///Move every pending task of the board to another one
+fn move_pending_tasks(store: &dyn TaskStore, board_title: &str, board_id: u16) -> Result<()> {
+    display_message("info", "Select the destination Board", Color::Cyan);
+    let (to_title, to_id) = match select_board(store)? {
+        Some(board) => board,
+        None => return Ok(()),
+    };
+    if to_id == board_id {
+        return Err(Error::Validation(
+            "Destination must be another Board".to_string(),
+        ));
+    }
+
+    let moved = store.move_tasks(&TaskFilter::pending().board(board_id), to_id)?;
+    display_message(
+        "ok",
+        format!("{} tasks moved from {} to {}", moved, board_title, to_title).as_str(),
+        Color::Green,
+    );
+    Ok(())
+}
+
+fn delete_board(store: &dyn TaskStore, board_title: &str, board_id: u16) -> Result<()> {
+    let deletion_confirmation = get_user_confirmation(
+        format!("Are you sure you want to delete {}", &board_title).as_str(),
+    )?;
 
     if deletion_confirmation {
-        dao::delete_tasks_by_board_id(&board_id).unwrap();
-        let deletion_successful = dao::delete_record_by_id(TABLE_BOARDS, board_id);
-        match deletion_successful {
-            Ok(_) => display_message(
-                "ok",
-                format!("Board {} has been deleted", &board_title).as_str(),
-                Color::Green,
-            ),
-            Err(_) => display_message(
-                "error",
-                format!("Could not delete {} ", &board_title).as_str(),
-                Color::Red,
-            ),
-        }
+        store.delete_board(board_id)?;
+        display_message(
+            "ok",
+            format!("Board {} has been deleted", &board_title).as_str(),
+            Color::Green,
+        );
     }
     Ok(())
 }
 
-pub fn datetime_str_is_past(reminder: &str) -> bool {
-    let datetime = Utc.datetime_from_str(reminder, DATETIME_FORMAT);
+///Pending tasks whose due date has passed
+pub fn get_overdue_tasks(store: &dyn TaskStore) -> Result<Vec<Task>> {
+    let filter = TaskFilter {
+        due_before: Some(Utc::now()),
+        sort: TaskSort::Priority,
+        ..TaskFilter::pending()
+    };
+    store.list_tasks(&filter)
+}
+
+///Pending tasks not blocked by any pending task
+pub fn get_ready_tasks(store: &dyn TaskStore) -> Result<Vec<Task>> {
+    let filter = TaskFilter {
+        ready: true,
+        sort: TaskSort::Priority,
+        ..TaskFilter::pending()
+    };
+    store.list_tasks(&filter)
+}
 
-    if datetime.is_err() {
-        return false;
+pub fn list_ready_tasks(store: &dyn TaskStore) -> Result<()> {
+    let ready_tasks = get_ready_tasks(store)?;
+    if ready_tasks.is_empty() {
+        display_message("info", "No Tasks are ready", Color::Cyan);
+        return Ok(());
     }
-    datetime.unwrap() < Utc::now()
+    println!("{}", Table::new(ready_tasks).with(Style::modern()));
+    Ok(())
 }
 
-fn delete_task(task_title: &str, task_id: u16) -> Result<()> {
-    let deletion_confirmation =
-        get_user_confirmation(format!("Are you sure you want to delete {}", &task_title).as_str());
+pub fn list_overdue_tasks(store: &dyn TaskStore) -> Result<()> {
+    let overdue_tasks = get_overdue_tasks(store)?;
+
+    if overdue_tasks.is_empty() {
+        return Ok(());
+    }
+    println!("Overdue Tasks: {}", &overdue_tasks.len());
+
+    let table = Table::new(overdue_tasks).with(Style::modern());
+    println!("{}", disable_task_columns(table, &OVERDUE_HIDDEN_COLUMNS));
+    Ok(())
+}
+
+///Hide the columns of a task table with these headers
+fn disable_task_columns(mut table: Table, headers: &[&str]) -> Table {
+    let mut indexes: Vec<usize> = Task::headers()
+        .iter()
+        .enumerate()
+        .filter(|(_, x)| headers.contains(&x.as_str()))
+        .map(|(index, _)| index)
+        .collect();
+    //Last first, removing a column shifts the following ones
+    indexes.reverse();
+    for index in indexes {
+        table = table.with(Disable::Column(index..=index));
+    }
+    table
+}
+
+pub fn create_task(store: &dyn TaskStore) -> Result<()> {
+    let boards_qtd = store.count_records(TABLE_BOARDS)?;
+
+    if boards_qtd == 0 {
+        display_message("info", "Create a initial Board", Color::Cyan);
+        create_board(store)?;
+    }
+
+    let title = get_user_input("Task title", SAMPLE_TITLE, true)?;
+    let board_id = select_board(store)?
+        .ok_or_else(|| Error::NotFound("Board".to_string()))?
+        .1;
+    let priority = select_priority(Priority::Normal)?;
+    let with_due_date = get_user_confirmation("Set due date")?;
+
+    let due_at = match with_due_date {
+        true => Some(get_user_date(true)?),
+        false => None,
+    };
+
+    store.journaled(&format!("Create task {}", title), &mut || {
+        store
+            .add_task(&NewTask {
+                priority,
+                due_at,
+                ..NewTask::new(&title, board_id)
+            })
+            .map(|_| ())
+    })
+}
+
+///Same board and priority as the parent
+pub fn create_subtask(store: &dyn TaskStore, parent_id: u16) -> Result<()> {
+    let parent = store.get_task(parent_id)?;
+    let title = get_user_input("Subtask title", SAMPLE_TITLE, true)?;
+    store.add_task(&NewTask {
+        priority: parent.priority,
+        parent_id: Some(parent.id),
+        ..NewTask::new(&title, parent.board_id)
+    })?;
+    Ok(())
+}
+
+///Replace the tags of a task with a space separated list
+pub fn edit_tags(store: &dyn TaskStore, task_id: u16) -> Result<()> {
+    let task = store.get_task(task_id)?;
+    let text = get_user_input("Tags (space separated)", &task.tags.join(" "), true)?;
+    let tags = text
+        .split_whitespace()
+        .map(validate_tag_name)
+        .collect::<Result<Vec<String>>>()?;
+    store.set_task_tags(task_id, &tags)
+}
+
+///Select a pending task that must be done before this one
+pub fn add_blocker(store: &dyn TaskStore, task_id: u16) -> Result<()> {
+    display_message("info", "Select the blocking Task", Color::Cyan);
+    let (blocker_title, blocker_id) = match select_task(store, 0)? {
+        Some(task) => task,
+        None => return Ok(()),
+    };
+    store.add_dependency(task_id, blocker_id)?;
+    display_message(
+        "ok",
+        &format!("Task is now blocked by {}", blocker_title),
+        Color::Green,
+    );
+    Ok(())
+}
+
+fn delete_task(store: &dyn TaskStore, task_title: &str, task_id: u16) -> Result<()> {
+    let subtasks = store.get_task(task_id)?.subtasks.total;
+    let question = match subtasks {
+        0 => format!("Are you sure you want to delete {}", &task_title),
+        _ => format!(
+            "Are you sure you want to delete {} and its {} subtasks",
+            &task_title, subtasks
+        ),
+    };
+    let deletion_confirmation = get_user_confirmation(&question)?;
 
     if deletion_confirmation {
-        dao::delete_comments_by_task_id(&task_id).unwrap();
-        let deletion_successful = dao::delete_record_by_id(TABLE_TASKS, task_id);
-        match deletion_successful {
-            Ok(_) => display_message(
-                "ok",
-                format!("task {} has been deleted", &task_title).as_str(),
-                Color::Green,
-            ),
-            Err(_) => display_message(
-                "error",
-                format!("Could not delete {} ", &task_title).as_str(),
-                Color::Red,
-            ),
-        }
+        store.delete_task(task_id)?;
+        display_message(
+            "ok",
+            format!("task {} has been deleted", &task_title).as_str(),
+            Color::Green,
+        );
     }
     Ok(())
 }
 
-pub fn list_tasks(done: u8) -> Result<()> {
-    let selected_task = dao::select_task(done);
-    if selected_task.is_none() {
+pub fn switch_task_status(store: &dyn TaskStore, task_id: u16) -> Result<()> {
+    let done = get_user_confirmation("Done")?;
+    let open = count_open_subtasks(store, task_id)?;
+    if done && open > 0 {
+        let confirmation = get_user_confirmation(&format!(
+            "This task has {} open subtasks, mark them done too",
+            open
+        ))?;
+        if !confirmation {
+            return Ok(());
+        }
+        if let (_, Some(next)) = complete_with_subtasks(store, task_id)? {
+            display_next_occurrence(store, next)?;
+        }
         return Ok(());
     }
+    if let Some(next) = set_task_done(store, task_id, done)? {
+        display_next_occurrence(store, next)?;
+    }
+    Ok(())
+}
 
-    let (task_title, task_id) = selected_task.unwrap();
+///Complete the open subtasks and then the task, or nothing when the task can't be completed.
+///Returns how many subtasks were completed and the next occurrence of a recurring task
+pub fn complete_with_subtasks(store: &dyn TaskStore, task_id: u16) -> Result<(usize, Option<u16>)> {
+    let mut completed = 0;
+    let mut next = None;
+    store.transaction(true, &mut || {
+        completed = complete_subtasks(store, task_id)?;
+        next = set_task_done(store, task_id, true)?;
+        Ok(())
+    })?;
+    Ok((completed, next))
+}
 
-    let (_, action_index) = get_user_selection(
-        &TASK_ACTIONS.to_vec(),
-        format!("Action on Task {}", task_title).as_str(),
+fn count_open_subtasks(store: &dyn TaskStore, task_id: u16) -> Result<u16> {
+    let subtasks = store.get_task(task_id)?.subtasks;
+    Ok(subtasks.total - subtasks.done)
+}
+
+///Mark every open subtask done, theirs included. Returns how many were completed
+pub fn complete_subtasks(store: &dyn TaskStore, task_id: u16) -> Result<usize> {
+    let mut completed = 0;
+    for subtask in store.list_tasks(&TaskFilter::pending().parent(task_id))? {
+        completed += complete_subtasks(store, subtask.id)?;
+        set_task_done(store, subtask.id, true)?;
+        completed += 1;
+    }
+    Ok(completed)
+}
+
+///Mark a task done or pending. Completing a recurring task creates its next occurrence,
+///which takes over the recurrence and the reminders. Returns the id of that occurrence.
+///A task with open subtasks or pending blockers can't be completed, see `complete_subtasks`
+pub fn set_task_done(store: &dyn TaskStore, task_id: u16, done: bool) -> Result<Option<u16>> {
+    let mut task = store.get_task(task_id)?;
+    if done && task.blockers > 0 {
+        let blockers: Vec<String> = store
+            .list_blockers(task_id)?
+            .iter()
+            .filter(|x| x.done == 0)
+            .map(|x| x.id.to_string())
+            .collect();
+        return Err(Error::Validation(format!(
+            "Task {} is blocked by tasks {}",
+            task_id,
+            blockers.join(", ")
+        )));
+    }
+    let open = task.subtasks.total - task.subtasks.done;
+    if done && open > 0 {
+        return Err(Error::Validation(format!(
+            "Task {} has {} open subtasks",
+            task_id, open
+        )));
+    }
+    let recurrence = match done && task.done == 0 {
+        true => task.recurrence.take(),
+        false => None,
+    };
+    //Tasks without due date repeat from the day they are completed
+    let now = Utc::now();
+    let previous_due = task.due_at.unwrap_or(now);
+    let next_due = match &recurrence {
+        Some(recurrence) => Some(recurrence.next_occurrence(previous_due, now).ok_or_else(
+            || Error::Validation(format!("Task {} has no next occurrence", task_id)),
+        )?),
+        None => None,
+    };
+    task.done = done as u8;
+
+    //The task stays pending unless its next occurrence is created
+    let mut next_id = None;
+    store.transaction(true, &mut || {
+        store.update_task(&task)?;
+        let (Some(recurrence), Some(due_at)) = (&recurrence, next_due) else {
+            return Ok(());
+        };
+        let id = store.add_task(&NewTask {
+            title: task.title.clone(),
+            board_id: task.board_id,
+            priority: task.priority,
+            due_at: Some(due_at),
+            recurrence: Some(recurrence.anchored(&previous_due.with_timezone(&Local))),
+            parent_id: task.parent_id,
+            column_id: None,
+            created_at: None,
+            completed_at: None,
+        })?;
+        store.set_task_tags(id, &task.tags)?;
+
+        for reminder in store.list_reminders(task.id)? {
+            let time = match reminder.before_due {
+                Some(before_due) => ReminderTime::BeforeDue(before_due),
+                None => ReminderTime::At(reminder.remind_at + (due_at - previous_due)),
+            };
+            store.add_reminder(id, time)?;
+        }
+        next_id = Some(id);
+        Ok(())
+    })?;
+    Ok(next_id)
+}
+
+pub fn display_next_occurrence(store: &dyn TaskStore, task_id: u16) -> Result<()> {
+    let task = store.get_task(task_id)?;
+    display_message(
+        "info",
+        &format!(
+            "Next occurrence is Task {} due {}",
+            task.id,
+            task.due_at
+                .as_ref()
+                .map(format_datetime)
+                .unwrap_or_default()
+        ),
+        Color::Cyan,
     );
+    Ok(())
+}
 
-    match action_index {
-        0 => delete_task(&task_title, task_id)?,
-        1 => dao::switch_task_status(task_id)?,
-        2 => dao::create_comment(task_id)?,
-        3 => list_comments(&task_title, task_id)?,
-        4 => dao::set_reminder(task_id)?,
-        _ => return Ok(()),
+///Set, change or stop the recurrence of a task
+pub fn set_recurrence(store: &dyn TaskStore, task_id: u16) -> Result<()> {
+    let mut task = store.get_task(task_id)?;
+    let current = match &task.recurrence {
+        Some(recurrence) => recurrence.describe(),
+        None => NO_RECURRENCE.to_string(),
     };
+    let text = get_user_input(
+        &format!(
+            "Repeat (e.g. {}, or {})",
+            RECURRENCE_EXAMPLES, NO_RECURRENCE
+        ),
+        &current,
+        true,
+    )?;
+    task.recurrence = match text.trim() {
+        NO_RECURRENCE => None,
+        text => Some(text.parse()?),
+    };
+    store.update_task(&task)
+}
+
+pub fn set_due_date(store: &dyn TaskStore, task_id: u16) -> Result<()> {
+    let due_at = Some(get_user_date(false)?);
+    let mut task = store.get_task(task_id)?;
+    task.due_at = due_at;
+    store.update_task(&task)
+}
 
+pub fn add_reminder(store: &dyn TaskStore, task_id: u16) -> Result<()> {
+    let task = store.get_task(task_id)?;
+    let reminders = store.list_reminders(task_id)?;
+    if !reminders.is_empty() {
+        println!("{}", Table::new(reminders).with(Style::modern()));
+    }
+
+    let default = match task.due_at {
+        Some(_) => "1h before",
+        None => "tomorrow 9am",
+    };
+    let text = get_user_input(
+        &format!("Remind (e.g. {})", REMINDER_EXAMPLES),
+        default,
+        true,
+    )?;
+    store.add_reminder(task_id, parse_reminder_time(&text)?)?;
+    Ok(())
+}
+
+pub fn select_priority(current: Priority) -> Result<Priority> {
+    let selection = get_user_selection_index(&PRIORITIES, "Priority", current as usize)?;
+    Ok(PRIORITIES[selection])
+}
+
+pub fn set_priority(store: &dyn TaskStore, task_id: u16) -> Result<()> {
+    let mut task = store.get_task(task_id)?;
+    task.priority = select_priority(task.priority)?;
+    store.update_task(&task)
+}
+
+pub fn edit_task_title(store: &dyn TaskStore, task_id: u16) -> Result<()> {
+    let mut task = store.get_task(task_id)?;
+    task.title = get_user_input("New Task title", &task.title, true)?;
+    store.update_task(&task)
+}
+
+pub fn move_task(store: &dyn TaskStore, task_id: u16) -> Result<()> {
+    let mut task = store.get_task(task_id)?;
+    let (board_title, board_id) = match select_board(store)? {
+        Some(board) => board,
+        None => return Ok(()),
+    };
+    task.board_id = board_id;
+    store.update_task(&task)?;
+    display_message(
+        "ok",
+        format!("Task {} moved to {}", task.title, board_title).as_str(),
+        Color::Green,
+    );
+    Ok(())
+}
+
+pub fn create_comment(store: &dyn TaskStore, task_id: u16) -> Result<()> {
+    let title = get_user_input("Comment title", SAMPLE_TITLE, true)?;
+    store.add_comment(task_id, &title)?;
+    Ok(())
+}
+
+pub fn list_tasks(store: &dyn TaskStore, done: u8) -> Result<()> {
+    let Some((task_title, task_id)) = select_task(store, done)? else {
+        return Ok(());
+    };
+
+    let (action, action_index) = get_user_selection(
+        &TASK_ACTIONS,
+        format!("Action on Task {}", task_title).as_str(),
+    )?;
+
+    let description = format!("{}: task {} {}", action, task_id, task_title);
+    let journaled = |f: &mut dyn FnMut() -> Result<()>| store.journaled(&description, f);
+    match action_index {
+        0 => journaled(&mut || delete_task(store, &task_title, task_id)),
+        1 => journaled(&mut || switch_task_status(store, task_id)),
+        2 => journaled(&mut || create_comment(store, task_id)),
+        3 => list_comments(store, &task_title, task_id),
+        4 => journaled(&mut || set_due_date(store, task_id)),
+        5 => journaled(&mut || add_reminder(store, task_id)),
+        6 => journaled(&mut || edit_task_title(store, task_id)),
+        7 => journaled(&mut || move_task(store, task_id)),
+        8 => journaled(&mut || set_priority(store, task_id)),
+        9 => journaled(&mut || set_recurrence(store, task_id)),
+        10 => journaled(&mut || create_subtask(store, task_id)),
+        11 => journaled(&mut || add_blocker(store, task_id)),
+        12 => journaled(&mut || edit_tags(store, task_id)),
+        13 => journaled(&mut || select_column(store, task_id)),
+        14 => list_history(store, task_id),
+        _ => Ok(()),
+    }
+}
+
+pub fn search_tasks(store: &dyn TaskStore) -> Result<()> {
+    let text = get_user_input("Search", "", true)?;
+    let results = store.search(&text, search::DEFAULT_SEARCH_LIMIT)?;
+
+    if results.is_empty() {
+        display_message("info", "No Tasks match this search", Color::Cyan);
+        return Ok(());
+    }
+    search::display_search_results(&results);
+    Ok(())
+}
+
+///Changes of the task, oldest first
+pub fn list_history(store: &dyn TaskStore, task_id: u16) -> Result<()> {
+    let history = store.list_history(task_id)?;
+    if history.is_empty() {
+        store.get_task(task_id)?;
+        display_message("info", "No history for this Task", Color::Cyan);
+        return Ok(());
+    }
+    println!("{}", Table::new(history).with(Style::modern()));
     Ok(())
 }
 
-pub fn list_comments(task_title: &str, task_id: u16) -> Result<()> {
-    let comments = dao::get_comments_by_task_id(task_id)?;
-    if comments.len() == 0 {
+///Revert up to `count` operations, latest first
+pub fn undo_operations(store: &dyn TaskStore, count: u32) -> Result<()> {
+    for _ in 0..count {
+        match store.undo()? {
+            Some(operation) => display_message(
+                "ok",
+                &format!("Undone: {}", operation.description),
+                Color::Green,
+            ),
+            None => {
+                display_message("info", "Nothing to undo", Color::Cyan);
+                break;
+            }
+        }
+    }
+    Ok(())
+}
+
+///Apply again up to `count` undone operations, in their original order
+pub fn redo_operations(store: &dyn TaskStore, count: u32) -> Result<()> {
+    for _ in 0..count {
+        match store.redo()? {
+            Some(operation) => display_message(
+                "ok",
+                &format!("Redone: {}", operation.description),
+                Color::Green,
+            ),
+            None => {
+                display_message("info", "Nothing to redo", Color::Cyan);
+                break;
+            }
+        }
+    }
+    Ok(())
+}
+
+///Latest operations first
+pub fn list_operations(store: &dyn TaskStore, limit: u32) -> Result<()> {
+    let operations = store.list_operations(limit)?;
+    if operations.is_empty() {
+        display_message("info", "No operations to undo", Color::Cyan);
+        return Ok(());
+    }
+    println!("{}", Table::new(operations).with(Style::modern()));
+    Ok(())
+}
+
+pub fn list_comments(store: &dyn TaskStore, task_title: &str, task_id: u16) -> Result<()> {
+    let comments = store.list_comments(task_id)?;
+    if comments.is_empty() {
         display_message("info", "No comments for this Task", Color::Cyan);
         return Ok(());
     }
 
-    let selected_comment = select_comment(&comments, &task_title).unwrap();
+    let selected_comment = select_comment(&comments, task_title)?;
     println!("{:?}", selected_comment);
 
     Ok(())
@@ -293,40 +1124,21 @@ pub fn display_message(message_type: &str, message: &str, color: Color) {
     println!("{msg}");
 }
 
-pub fn get_tasks(query: &str) -> Result<Vec<Task>> {
-    let conn = get_connection();
-
-    let mut records: Vec<Task> = Vec::new();
-
-    let mut stmt = conn.prepare(&query)?;
-
-    let result_iter = stmt.query_map([], |row| {
-        Ok(Task {
-            id: row.get(0)?,
-            title: row.get(1)?,
-            done: row.get(2)?,
-            board_id: row.get(3)?,
-            created_at: row.get(4)?,
-            reminder: row.get(5)?,
-        })
-    })?;
-
-    for i in result_iter {
-        records.push(i?);
-    }
-
-    Ok(records)
+pub fn display_error(error: &Error) {
+    display_message("error", &error.to_string(), Color::Red);
 }
 
 pub fn display_app_intro() {
+    let location = get_database_location();
     let title = format!(
-        "\n{} - {} \nAuthors: {}\nVersion: {}\nLicense: {}\nCrafted with ❤️ using Rust language\nDatabase: {}\n",
+        "\n{} - {} \nAuthors: {}\nVersion: {}\nLicense: {}\nCrafted with ❤️ using Rust language\nDatabase: {} (from {})\n",
         env!("CARGO_PKG_NAME").to_uppercase(),
         env!("CARGO_PKG_DESCRIPTION"),
         env!("CARGO_PKG_AUTHORS"),
         env!("CARGO_PKG_VERSION"),
         env!("CARGO_PKG_LICENSE"),
-        get_database_path()
+        location.path.display(),
+        location.source
     );
 
     println!("{title}");
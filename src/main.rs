@@ -1,25 +1,74 @@
-use rusqlite::Result;
-use task_manager::*;
-pub mod dao;
-pub mod user_input;
+use clap::Parser;
+use std::process;
+use task_manager::{
+    cli::Cli,
+    error::{Error, Result},
+    store::TaskStore,
+    *,
+};
+
+fn main() {
+    if let Err(error) = run() {
+        display_error(&error);
+        process::exit(1);
+    }
+}
+
+fn run() -> Result<()> {
+    let cli = Cli::parse();
+    set_database_location(config::resolve_database_location(cli.db)?);
+
+    if let Some(command) = cli.command {
+        let store = open_store()?;
+        return cli::run(&store, command);
+    }
 
-fn main() -> Result<()> {
     display_app_intro();
-    dao::create_database()?;
-    dao::list_delayed_tasks();
+    let store = open_store()?;
+    warn_orphans(&store)?;
+    list_overdue_tasks(&store)?;
 
     loop {
-        let action = user_input::get_user_selection_text(&MAIN_MENU_OPTIONS.to_vec(), "Option");
-
-        match action.as_str() {
-            CREATE_TASK => dao::create_task()?,
-            VIEW_PENDING_TASKS => list_tasks(0)?,
-            VIEW_DONE_TASKS => list_tasks(1)?,
-            CREATE_BOARD => dao::create_board()?,
-            VIEW_BOARDS => list_boards()?,
+        let action = match user_input::get_user_selection_text(&MAIN_MENU_OPTIONS, "Option") {
+            Err(Error::InputCancelled) => break,
+            action => action?,
+        };
+
+        let result = match action.as_str() {
+            CREATE_TASK => create_task(&store),
+            VIEW_PENDING_TASKS => list_tasks(&store, 0),
+            VIEW_DONE_TASKS => list_tasks(&store, 1),
+            VIEW_READY_TASKS => list_ready_tasks(&store),
+            SEARCH_TASKS => search_tasks(&store),
+            CREATE_BOARD => create_board(&store),
+            VIEW_BOARDS => list_boards(&store),
+            UNDO => undo_operations(&store, 1),
+            REDO => redo_operations(&store, 1),
             _ => break,
         };
+
+        match result {
+            Err(error) if error.is_fatal() => return Err(error),
+            Err(error) => display_error(&error),
+            Ok(_) => (),
+        }
     }
 
     Ok(())
 }
+
+fn warn_orphans(store: &dyn TaskStore) -> Result<()> {
+    let orphans = store.check_integrity()?;
+    if !orphans.is_empty() {
+        display_message(
+            "warning",
+            &format!(
+                "{} orphan rows found, run `{} check --repair` to delete them",
+                orphans.len(),
+                env!("CARGO_PKG_NAME")
+            ),
+            Color::Yellow,
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,472 @@
+use crate::{
+    error::{Error, Result},
+    TABLE_BOARDS, TABLE_COLUMNS, TABLE_COMMENTS, TABLE_COMMENTS_SEARCH, TABLE_DEPENDENCIES,
+    TABLE_HISTORY, TABLE_JOURNAL, TABLE_OPERATIONS, TABLE_REMINDERS, TABLE_TAGS, TABLE_TASKS,
+    TABLE_TASKS_SEARCH, TABLE_TASK_TAGS,
+};
+use chrono::{DateTime, TimeZone, Utc};
+use rusqlite::{params, Connection};
+
+///A schema change, applied once and recorded in PRAGMA user_version
+pub struct Migration {
+    pub description: &'static str,
+    pub up: fn(&Connection) -> rusqlite::Result<()>,
+}
+
+///Ordered schema history. Never edit or reorder an entry, only append new ones
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        description: "Create boards, tasks and comments tables",
+        up: create_tables,
+    },
+    Migration {
+        description: "Cascade deletes from boards to tasks and from tasks to comments",
+        up: cascade_deletes,
+    },
+    Migration {
+        description: "Full-text index over task and comment titles",
+        up: search_index,
+    },
+    Migration {
+        description: "Task priority",
+        up: task_priority,
+    },
+    Migration {
+        description: "ISO-8601 UTC timestamps, tasks without reminder have none",
+        up: iso_timestamps,
+    },
+    Migration {
+        description: "Task recurrence rule",
+        up: task_recurrence,
+    },
+    Migration {
+        description: "Record when a reminder was notified",
+        up: reminder_fired,
+    },
+    Migration {
+        description: "Due dates and multiple reminders per task",
+        up: due_dates_and_reminders,
+    },
+    Migration {
+        description: "Subtasks, deleted with their parent task",
+        up: subtasks,
+    },
+    Migration {
+        description: "Dependencies between tasks",
+        up: task_dependencies,
+    },
+    Migration {
+        description: "Task tags",
+        up: task_tags,
+    },
+    Migration {
+        description: "Ordered columns per board, done tasks in the terminal one",
+        up: board_columns,
+    },
+    Migration {
+        description: "Task completion time and history",
+        up: task_history,
+    },
+    Migration {
+        description: "Operation journal for undo and redo",
+        up: operation_journal,
+    },
+    Migration {
+        description: "Task ids are never reused",
+        up: task_autoincrement,
+    },
+];
+
+///Format tasks.created_at and reminders were stored in before ISO-8601
+const LEGACY_DATETIME_FORMAT: &str = "%a, %b %e %Y %T";
+
+///Schema version this binary understands
+pub fn latest_version() -> u32 {
+    MIGRATIONS.len() as u32
+}
+
+pub fn get_schema_version(conn: &Connection) -> Result<u32> {
+    let version = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    Ok(version)
+}
+
+///Apply every pending migration in a single transaction, returning the new schema version.
+///Foreign keys are off while migrating so tables can be rebuilt
+pub fn migrate(conn: &mut Connection) -> Result<u32> {
+    let current = get_schema_version(conn)?;
+    let latest = latest_version();
+
+    if current > latest {
+        return Err(Error::SchemaTooNew {
+            found: current,
+            supported: latest,
+        });
+    }
+    if current == latest {
+        return Ok(current);
+    }
+
+    conn.pragma_update(None, "foreign_keys", false)?;
+    let result = apply_migrations(conn, current, latest);
+    conn.pragma_update(None, "foreign_keys", true)?;
+    result?;
+
+    Ok(latest)
+}
+
+fn apply_migrations(conn: &mut Connection, current: u32, latest: u32) -> Result<()> {
+    let tx = conn.transaction()?;
+    for migration in &MIGRATIONS[current as usize..] {
+        (migration.up)(&tx).map_err(|source| Error::Migration {
+            description: migration.description,
+            source,
+        })?;
+    }
+    tx.pragma_update(None, "user_version", latest)?;
+    tx.commit()?;
+    Ok(())
+}
+
+//Tables may already exist in databases created before versioning
+fn create_tables(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(&format!(
+        "CREATE TABLE IF NOT EXISTS {TABLE_BOARDS} (
+              id              INTEGER PRIMARY KEY,
+              title           VARCHAR(255) NOT NULL
+              );
+        CREATE TABLE IF NOT EXISTS {TABLE_TASKS} (
+              id              INTEGER PRIMARY KEY,
+              title           VARCHAR(255) NOT NULL,
+              done              INTEGER NOT NULL,
+              board_id          INTEGER NOT NULL,
+              created_at           VARCHAR(255) NOT NULL,
+              reminder           VARCHAR(255) NOT NULL,
+              FOREIGN KEY(board_id) REFERENCES {TABLE_BOARDS}(id)
+            );
+        CREATE TABLE IF NOT EXISTS {TABLE_COMMENTS} (
+              id              INTEGER PRIMARY KEY,
+              title           VARCHAR(255) NOT NULL,
+              task_id          INTEGER NOT NULL,
+              created_at           VARCHAR(255) NOT NULL,
+              FOREIGN KEY(task_id) REFERENCES {TABLE_TASKS}(id)
+              );"
+    ))
+}
+
+//SQLite cannot alter a foreign key, so both tables are rebuilt. Orphans are kept for the integrity check
+fn cascade_deletes(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(&format!(
+        "CREATE TABLE {TABLE_TASKS}_new (
+              id              INTEGER PRIMARY KEY,
+              title           VARCHAR(255) NOT NULL,
+              done              INTEGER NOT NULL,
+              board_id          INTEGER NOT NULL,
+              created_at           VARCHAR(255) NOT NULL,
+              reminder           VARCHAR(255) NOT NULL,
+              FOREIGN KEY(board_id) REFERENCES {TABLE_BOARDS}(id) ON DELETE CASCADE
+            );
+        INSERT INTO {TABLE_TASKS}_new SELECT id, title, done, board_id, created_at, reminder FROM {TABLE_TASKS};
+        DROP TABLE {TABLE_TASKS};
+        ALTER TABLE {TABLE_TASKS}_new RENAME TO {TABLE_TASKS};
+
+        CREATE TABLE {TABLE_COMMENTS}_new (
+              id              INTEGER PRIMARY KEY,
+              title           VARCHAR(255) NOT NULL,
+              task_id          INTEGER NOT NULL,
+              created_at           VARCHAR(255) NOT NULL,
+              FOREIGN KEY(task_id) REFERENCES {TABLE_TASKS}(id) ON DELETE CASCADE
+              );
+        INSERT INTO {TABLE_COMMENTS}_new SELECT id, title, task_id, created_at FROM {TABLE_COMMENTS};
+        DROP TABLE {TABLE_COMMENTS};
+        ALTER TABLE {TABLE_COMMENTS}_new RENAME TO {TABLE_COMMENTS};
+        CREATE INDEX IF NOT EXISTS idx_{TABLE_TASKS}_board_id ON {TABLE_TASKS}(board_id);
+        CREATE INDEX IF NOT EXISTS idx_{TABLE_COMMENTS}_task_id ON {TABLE_COMMENTS}(task_id);"
+    ))
+}
+
+//External content FTS5 tables kept in sync with their source table by triggers
+fn search_index(conn: &Connection) -> rusqlite::Result<()> {
+    for (table, index) in [
+        (TABLE_TASKS, TABLE_TASKS_SEARCH),
+        (TABLE_COMMENTS, TABLE_COMMENTS_SEARCH),
+    ] {
+        conn.execute_batch(&format!(
+            "CREATE VIRTUAL TABLE {index} USING fts5(
+                  title,
+                  content='{table}',
+                  content_rowid='id',
+                  tokenize='unicode61 remove_diacritics 2'
+                  );
+            INSERT INTO {index}({index}) VALUES ('rebuild');"
+        ))?;
+        create_search_triggers(conn, table, index)?;
+    }
+    Ok(())
+}
+
+fn create_search_triggers(conn: &Connection, table: &str, index: &str) -> rusqlite::Result<()> {
+    conn.execute_batch(&format!(
+        "CREATE TRIGGER {table}_search_insert AFTER INSERT ON {table} BEGIN
+            INSERT INTO {index}(rowid, title) VALUES (new.id, new.title);
+        END;
+        CREATE TRIGGER {table}_search_delete AFTER DELETE ON {table} BEGIN
+            INSERT INTO {index}({index}, rowid, title) VALUES ('delete', old.id, old.title);
+        END;
+        CREATE TRIGGER {table}_search_update AFTER UPDATE OF title ON {table} BEGIN
+            INSERT INTO {index}({index}, rowid, title) VALUES ('delete', old.id, old.title);
+            INSERT INTO {index}(rowid, title) VALUES (new.id, new.title);
+        END;"
+    ))
+}
+
+//Existing tasks become normal priority
+fn task_priority(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(&format!(
+        "ALTER TABLE {TABLE_TASKS} ADD COLUMN priority INTEGER NOT NULL DEFAULT 1;"
+    ))
+}
+
+//Values are converted in Rust since SQLite can't parse the legacy formats. The tasks table is
+//rebuilt so reminder can be NULL, which drops its search triggers
+fn iso_timestamps(conn: &Connection) -> rusqlite::Result<()> {
+    let tasks = read_timestamps(
+        conn,
+        &format!("SELECT id, created_at, reminder FROM {TABLE_TASKS}"),
+    )?;
+    let comments = read_timestamps(
+        conn,
+        &format!("SELECT id, created_at, '' FROM {TABLE_COMMENTS}"),
+    )?;
+
+    conn.execute_batch(&format!(
+        "CREATE TABLE {TABLE_TASKS}_new (
+              id              INTEGER PRIMARY KEY,
+              title           VARCHAR(255) NOT NULL,
+              done              INTEGER NOT NULL,
+              board_id          INTEGER NOT NULL,
+              created_at           TEXT NOT NULL,
+              reminder           TEXT,
+              priority          INTEGER NOT NULL DEFAULT 1,
+              FOREIGN KEY(board_id) REFERENCES {TABLE_BOARDS}(id) ON DELETE CASCADE
+            );
+        INSERT INTO {TABLE_TASKS}_new SELECT id, title, done, board_id, created_at, NULL, priority FROM {TABLE_TASKS};
+        DROP TABLE {TABLE_TASKS};
+        ALTER TABLE {TABLE_TASKS}_new RENAME TO {TABLE_TASKS};
+        CREATE INDEX idx_{TABLE_TASKS}_board_id ON {TABLE_TASKS}(board_id);"
+    ))?;
+    create_search_triggers(conn, TABLE_TASKS, TABLE_TASKS_SEARCH)?;
+
+    for (id, created_at, reminder) in tasks {
+        conn.execute(
+            &format!("UPDATE {TABLE_TASKS} SET created_at = ?1, reminder = ?2 WHERE id = ?3"),
+            params![created_at, reminder, id],
+        )?;
+    }
+    for (id, created_at, _) in comments {
+        conn.execute(
+            &format!("UPDATE {TABLE_COMMENTS} SET created_at = ?1 WHERE id = ?2"),
+            params![created_at, id],
+        )?;
+    }
+    Ok(())
+}
+
+type Timestamps = (i64, DateTime<Utc>, Option<DateTime<Utc>>);
+
+///id, created_at and an optional reminder, failing on values that can't be read
+fn read_timestamps(conn: &Connection, query: &str) -> rusqlite::Result<Vec<Timestamps>> {
+    let mut stmt = conn.prepare(query)?;
+    let rows = stmt.query_map([], |row| {
+        let id: i64 = row.get(0)?;
+        let created_at: String = row.get(1)?;
+        let reminder: String = row.get(2)?;
+        let reminder = match reminder.is_empty() {
+            true => None,
+            false => Some(parse_legacy_datetime(id, &reminder)?),
+        };
+        Ok((id, parse_legacy_datetime(id, &created_at)?, reminder))
+    })?;
+    rows.collect()
+}
+
+fn parse_legacy_datetime(id: i64, datetime: &str) -> rusqlite::Result<DateTime<Utc>> {
+    Utc.datetime_from_str(datetime, LEGACY_DATETIME_FORMAT)
+        .or_else(|_| DateTime::parse_from_rfc2822(datetime).map(|x| x.with_timezone(&Utc)))
+        .map_err(|_| {
+            rusqlite::Error::ToSqlConversionFailure(
+                format!("invalid datetime '{}' in row {}", datetime, id).into(),
+            )
+        })
+}
+
+//RRULE text, NULL for one-off tasks
+fn task_recurrence(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(&format!(
+        "ALTER TABLE {TABLE_TASKS} ADD COLUMN recurrence TEXT;"
+    ))
+}
+
+//Existing reminders count as not notified yet
+fn reminder_fired(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(&format!(
+        "ALTER TABLE {TABLE_TASKS} ADD COLUMN reminder_fired_at TEXT;"
+    ))
+}
+
+//The single reminder doubled as a deadline, so it becomes both the due date and a reminder at due
+fn due_dates_and_reminders(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(&format!(
+        "CREATE TABLE {TABLE_REMINDERS} (
+              id              INTEGER PRIMARY KEY,
+              task_id          INTEGER NOT NULL,
+              remind_at           TEXT NOT NULL,
+              before_due          INTEGER,
+              fired_at           TEXT,
+              FOREIGN KEY(task_id) REFERENCES {TABLE_TASKS}(id) ON DELETE CASCADE
+              );
+        CREATE INDEX idx_{TABLE_REMINDERS}_task_id ON {TABLE_REMINDERS}(task_id);
+        CREATE INDEX idx_{TABLE_REMINDERS}_remind_at ON {TABLE_REMINDERS}(remind_at);
+
+        ALTER TABLE {TABLE_TASKS} ADD COLUMN due_at TEXT;
+        UPDATE {TABLE_TASKS} SET due_at = reminder;
+        INSERT INTO {TABLE_REMINDERS} (task_id, remind_at, before_due, fired_at)
+            SELECT id, reminder, 0, reminder_fired_at FROM {TABLE_TASKS} WHERE reminder IS NOT NULL;
+        ALTER TABLE {TABLE_TASKS} DROP COLUMN reminder;
+        ALTER TABLE {TABLE_TASKS} DROP COLUMN reminder_fired_at;"
+    ))
+}
+
+fn subtasks(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(&format!(
+        "ALTER TABLE {TABLE_TASKS} ADD COLUMN parent_id INTEGER REFERENCES {TABLE_TASKS}(id) ON DELETE CASCADE;
+        CREATE INDEX idx_{TABLE_TASKS}_parent_id ON {TABLE_TASKS}(parent_id);"
+    ))
+}
+
+//task_id waits on blocker_id
+fn task_dependencies(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(&format!(
+        "CREATE TABLE {TABLE_DEPENDENCIES} (
+              task_id          INTEGER NOT NULL,
+              blocker_id          INTEGER NOT NULL,
+              PRIMARY KEY(task_id, blocker_id),
+              FOREIGN KEY(task_id) REFERENCES {TABLE_TASKS}(id) ON DELETE CASCADE,
+              FOREIGN KEY(blocker_id) REFERENCES {TABLE_TASKS}(id) ON DELETE CASCADE
+              );
+        CREATE INDEX idx_{TABLE_DEPENDENCIES}_blocker_id ON {TABLE_DEPENDENCIES}(blocker_id);"
+    ))
+}
+
+fn task_tags(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(&format!(
+        "CREATE TABLE {TABLE_TAGS} (
+              id              INTEGER PRIMARY KEY,
+              name           TEXT NOT NULL UNIQUE COLLATE NOCASE
+              );
+        CREATE TABLE {TABLE_TASK_TAGS} (
+              task_id          INTEGER NOT NULL,
+              tag_id          INTEGER NOT NULL,
+              PRIMARY KEY(task_id, tag_id),
+              FOREIGN KEY(task_id) REFERENCES {TABLE_TASKS}(id) ON DELETE CASCADE,
+              FOREIGN KEY(tag_id) REFERENCES {TABLE_TAGS}(id) ON DELETE CASCADE
+              );
+        CREATE INDEX idx_{TABLE_TASK_TAGS}_tag_id ON {TABLE_TASK_TAGS}(tag_id);"
+    ))
+}
+
+//Boards get Todo, Doing and Done columns, pending tasks going to Todo
+fn board_columns(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(&format!(
+        "CREATE TABLE {TABLE_COLUMNS} (
+              id              INTEGER PRIMARY KEY,
+              board_id          INTEGER NOT NULL,
+              title           TEXT NOT NULL,
+              position          INTEGER NOT NULL,
+              terminal          INTEGER NOT NULL DEFAULT 0,
+              FOREIGN KEY(board_id) REFERENCES {TABLE_BOARDS}(id) ON DELETE CASCADE
+              );
+        CREATE INDEX idx_{TABLE_COLUMNS}_board_id ON {TABLE_COLUMNS}(board_id);
+        INSERT INTO {TABLE_COLUMNS} (board_id, title, position, terminal) SELECT id, 'Todo', 0, 0 FROM {TABLE_BOARDS};
+        INSERT INTO {TABLE_COLUMNS} (board_id, title, position, terminal) SELECT id, 'Doing', 1, 0 FROM {TABLE_BOARDS};
+        INSERT INTO {TABLE_COLUMNS} (board_id, title, position, terminal) SELECT id, 'Done', 2, 1 FROM {TABLE_BOARDS};
+
+        ALTER TABLE {TABLE_TASKS} ADD COLUMN column_id INTEGER REFERENCES {TABLE_COLUMNS}(id);
+        UPDATE {TABLE_TASKS} SET column_id = (
+            SELECT id FROM {TABLE_COLUMNS}
+            WHERE board_id = {TABLE_TASKS}.board_id AND terminal = ({TABLE_TASKS}.done = 1)
+            ORDER BY position LIMIT 1
+        );
+        CREATE INDEX idx_{TABLE_TASKS}_column_id ON {TABLE_TASKS}(column_id);"
+    ))
+}
+
+//Completion time of tasks done before is unknown. History rows outlive their task
+fn task_history(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(&format!(
+        "ALTER TABLE {TABLE_TASKS} ADD COLUMN completed_at TEXT;
+        CREATE TABLE {TABLE_HISTORY} (
+              id              INTEGER PRIMARY KEY,
+              task_id          INTEGER NOT NULL,
+              action           TEXT NOT NULL,
+              field           TEXT NOT NULL DEFAULT '',
+              old_value           TEXT,
+              new_value           TEXT,
+              created_at          TEXT NOT NULL
+              );
+        CREATE INDEX idx_{TABLE_HISTORY}_task_id ON {TABLE_HISTORY}(task_id);"
+    ))
+}
+
+//Each journal row is a statement reverting one row change of its operation
+fn operation_journal(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(&format!(
+        "CREATE TABLE {TABLE_OPERATIONS} (
+              id              INTEGER PRIMARY KEY,
+              description           TEXT NOT NULL,
+              created_at          TEXT NOT NULL,
+              undone          INTEGER NOT NULL DEFAULT 0
+              );
+        CREATE TABLE {TABLE_JOURNAL} (
+              id              INTEGER PRIMARY KEY,
+              operation_id          INTEGER NOT NULL,
+              statement           TEXT NOT NULL,
+              FOREIGN KEY(operation_id) REFERENCES {TABLE_OPERATIONS}(id) ON DELETE CASCADE
+              );
+        CREATE INDEX idx_{TABLE_JOURNAL}_operation_id ON {TABLE_JOURNAL}(operation_id);"
+    ))
+}
+
+//Rebuilt with AUTOINCREMENT so a new task doesn't take the id, and the history, of a deleted one.
+//The sequence starts after the ids already in the history
+fn task_autoincrement(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(&format!(
+        "CREATE TABLE {TABLE_TASKS}_new (
+              id              INTEGER PRIMARY KEY AUTOINCREMENT,
+              title           VARCHAR(255) NOT NULL,
+              done              INTEGER NOT NULL,
+              board_id          INTEGER NOT NULL,
+              created_at           TEXT NOT NULL,
+              priority          INTEGER NOT NULL DEFAULT 1,
+              recurrence          TEXT,
+              due_at          TEXT,
+              parent_id          INTEGER REFERENCES {TABLE_TASKS}(id) ON DELETE CASCADE,
+              column_id          INTEGER REFERENCES {TABLE_COLUMNS}(id),
+              completed_at          TEXT,
+              FOREIGN KEY(board_id) REFERENCES {TABLE_BOARDS}(id) ON DELETE CASCADE
+            );
+        INSERT INTO {TABLE_TASKS}_new (id, title, done, board_id, created_at, priority, recurrence, due_at, parent_id, column_id, completed_at)
+            SELECT id, title, done, board_id, created_at, priority, recurrence, due_at, parent_id, column_id, completed_at FROM {TABLE_TASKS};
+        DROP TABLE {TABLE_TASKS};
+        ALTER TABLE {TABLE_TASKS}_new RENAME TO {TABLE_TASKS};
+        CREATE INDEX idx_{TABLE_TASKS}_board_id ON {TABLE_TASKS}(board_id);
+        CREATE INDEX idx_{TABLE_TASKS}_parent_id ON {TABLE_TASKS}(parent_id);
+        CREATE INDEX idx_{TABLE_TASKS}_column_id ON {TABLE_TASKS}(column_id);
+
+        DELETE FROM sqlite_sequence WHERE name = '{TABLE_TASKS}';
+        INSERT INTO sqlite_sequence (name, seq) VALUES ('{TABLE_TASKS}', MAX(
+            (SELECT IFNULL(MAX(id), 0) FROM {TABLE_TASKS}),
+            (SELECT IFNULL(MAX(task_id), 0) FROM {TABLE_HISTORY})
+        ));"
+    ))?;
+    create_search_triggers(conn, TABLE_TASKS, TABLE_TASKS_SEARCH)
+}
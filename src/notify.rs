@@ -0,0 +1,162 @@
+use crate::{
+    config::NotificationConfig,
+    dates::{format_datetime, format_relative},
+    display_error, display_message,
+    error::{Error, Result},
+    store::TaskStore,
+    Color, Task,
+};
+use chrono::{DateTime, Utc};
+use std::{
+    io::{self, Write},
+    process::Command,
+    thread,
+    time::Duration,
+};
+
+pub const DESKTOP_NOTIFIER: &str = "notify-send";
+///Snooze used when no date is given
+pub const DEFAULT_SNOOZE: &str = "+10m";
+
+///A way of announcing a due reminder, besides the line printed in the terminal
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Notifier {
+    Desktop,
+    Bell,
+    Command(String),
+}
+
+impl Notifier {
+    ///Notifiers enabled in the config
+    pub fn from_config(config: &NotificationConfig) -> Vec<Notifier> {
+        let mut notifiers = Vec::new();
+        if config.desktop {
+            notifiers.push(Notifier::Desktop);
+        }
+        if config.bell {
+            notifiers.push(Notifier::Bell);
+        }
+        if let Some(command) = &config.command {
+            notifiers.push(Notifier::Command(command.clone()));
+        }
+        notifiers
+    }
+
+    pub fn notify(&self, task: &Task) -> Result<()> {
+        let due_at = task
+            .due_at
+            .as_ref()
+            .map(format_datetime)
+            .unwrap_or_default();
+        let status = match self {
+            Notifier::Desktop => Command::new(DESKTOP_NOTIFIER)
+                .arg(format!("--app-name={}", env!("CARGO_PKG_NAME")))
+                .arg(&task.title)
+                .arg(describe_due(task, Utc::now()))
+                .status(),
+            Notifier::Bell => {
+                print!("\x07");
+                io::stdout().flush()?;
+                return Ok(());
+            }
+            Notifier::Command(command) => Command::new("sh")
+                .arg("-c")
+                .arg(command)
+                .env("TASK_ID", task.id.to_string())
+                .env("TASK_TITLE", &task.title)
+                .env("TASK_DUE", due_at)
+                .status(),
+        };
+
+        //Reported as validation errors, a missing notify-send or a failing hook must not stop the watch
+        let status =
+            status.map_err(|e| Error::Validation(format!("{} failed: {}", self.name(), e)))?;
+        match status.success() {
+            true => Ok(()),
+            false => Err(Error::Validation(format!(
+                "{} exited with {}",
+                self.name(),
+                status
+            ))),
+        }
+    }
+
+    fn name(&self) -> &str {
+        match self {
+            Notifier::Desktop => DESKTOP_NOTIFIER,
+            Notifier::Bell => "bell",
+            Notifier::Command(command) => command,
+        }
+    }
+}
+
+///"Task 3 is due in 1h"
+fn describe_due(task: &Task, now: DateTime<Utc>) -> String {
+    match &task.due_at {
+        Some(due_at) if *due_at < now => {
+            format!("Task {} was due {}", task.id, format_relative(due_at, &now))
+        }
+        Some(due_at) => format!("Task {} is due {}", task.id, format_relative(due_at, &now)),
+        None => format!("Task {}", task.id),
+    }
+}
+
+///Notify each due reminder once and record it as fired. Returns the task of each reminder
+pub fn fire_due_reminders(
+    store: &dyn TaskStore,
+    notifiers: &[Notifier],
+    now: DateTime<Utc>,
+) -> Result<Vec<Task>> {
+    let mut tasks = Vec::new();
+
+    for mut reminder in store.list_due_reminders(now)? {
+        let task = store.get_task(reminder.task_id)?;
+        display_message(
+            "reminder",
+            &format!(
+                "{} - {}. Snooze with `{} task snooze {}`",
+                describe_due(&task, now),
+                task.title,
+                env!("CARGO_PKG_NAME"),
+                task.id
+            ),
+            Color::Magenta,
+        );
+        for notifier in notifiers {
+            if let Err(error) = notifier.notify(&task) {
+                display_error(&error);
+            }
+        }
+        reminder.fired_at = Some(now);
+        store.update_reminder(&reminder)?;
+        tasks.push(task);
+    }
+    Ok(tasks)
+}
+
+///Poll the database for due reminders until interrupted, or a single time with `once`
+pub fn watch(store: &dyn TaskStore, config: &NotificationConfig, once: bool) -> Result<()> {
+    let notifiers = Notifier::from_config(config);
+    if !once {
+        display_message(
+            "info",
+            &format!(
+                "Watching reminders every {}s, press Ctrl+C to stop",
+                config.interval
+            ),
+            Color::Cyan,
+        );
+    }
+
+    loop {
+        match fire_due_reminders(store, &notifiers, Utc::now()) {
+            Err(error) if error.is_fatal() => return Err(error),
+            Err(error) => display_error(&error),
+            Ok(_) => (),
+        }
+        if once {
+            return Ok(());
+        }
+        thread::sleep(Duration::from_secs(config.interval.max(1)));
+    }
+}
@@ -0,0 +1,443 @@
+use crate::error::{Error, Result};
+use chrono::{DateTime, Datelike, Duration, Local, Months, NaiveDate, TimeZone, Utc, Weekday};
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, ValueRef};
+use std::{fmt, str::FromStr};
+
+///Input that stops a series
+pub const NO_RECURRENCE: &str = "never";
+///Shown to the user when asking for a recurrence
+pub const RECURRENCE_EXAMPLES: &str =
+    "daily, every 3 days, weekly on mon,fri, monthly on 15, FREQ=WEEKLY;BYDAY=MO";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+///Subset of an iCalendar RRULE: FREQ, INTERVAL, BYDAY for weekly and BYMONTHDAY for monthly rules.
+///Stored as the RRULE text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recurrence {
+    pub frequency: Frequency,
+    ///Repeat every `interval` days, weeks or months
+    pub interval: u32,
+    ///Weekly only. Empty repeats on the weekday of the previous occurrence
+    pub weekdays: Vec<Weekday>,
+    ///Monthly only, clamped to the length of the month. None repeats on the day of the previous
+    ///occurrence, see `anchored`
+    pub month_day: Option<u32>,
+}
+
+impl Recurrence {
+    pub fn new(frequency: Frequency, interval: u32) -> Recurrence {
+        Recurrence {
+            frequency,
+            interval,
+            weekdays: Vec::new(),
+            month_day: None,
+        }
+    }
+
+    ///Next occurrence strictly after `previous`, at the same time of day
+    pub fn next_after<Tz: TimeZone>(&self, previous: &DateTime<Tz>) -> Option<DateTime<Tz>> {
+        let local = previous.naive_local();
+        let date = local.date();
+        let interval = self.interval as i64;
+
+        let next = match self.frequency {
+            Frequency::Daily => date + Duration::days(interval),
+            Frequency::Weekly if self.weekdays.is_empty() => date + Duration::weeks(interval),
+            Frequency::Weekly => {
+                let first_week = week_start(date);
+                (1..=7 * interval + 7)
+                    .map(|days| date + Duration::days(days))
+                    .find(|x| {
+                        let weeks = (week_start(*x) - first_week).num_weeks();
+                        weeks % interval == 0 && self.weekdays.contains(&x.weekday())
+                    })?
+            }
+            Frequency::Monthly => {
+                let day = self.month_day.unwrap_or_else(|| date.day());
+                let this_month = with_month_day(date, day)?;
+                match this_month > date && self.month_day.is_some() {
+                    true => this_month,
+                    false => with_month_day(
+                        date.with_day(1)?
+                            .checked_add_months(Months::new(self.interval))?,
+                        day,
+                    )?,
+                }
+            }
+        };
+
+        //A time skipped by a DST change moves forward by the gap
+        let timezone = previous.timezone();
+        let next = next.and_time(local.time());
+        timezone.from_local_datetime(&next).earliest().or_else(|| {
+            timezone
+                .from_local_datetime(&(next + Duration::hours(1)))
+                .earliest()
+        })
+    }
+
+    ///Monthly rules without a day repeat on the day of `start`, so a series starting on the
+    ///31st comes back to it after shorter months
+    pub fn anchored<Tz: TimeZone>(&self, start: &DateTime<Tz>) -> Recurrence {
+        match (self.frequency, self.month_day) {
+            (Frequency::Monthly, None) => Recurrence {
+                month_day: Some(start.naive_local().day()),
+                ..self.clone()
+            },
+            _ => self.clone(),
+        }
+    }
+
+    ///First occurrence after `now`, skipping the ones missed since `previous`. Computed in local time
+    pub fn next_occurrence(
+        &self,
+        previous: DateTime<Utc>,
+        now: DateTime<Utc>,
+    ) -> Option<DateTime<Utc>> {
+        let mut next = previous.with_timezone(&Local);
+        loop {
+            next = self.next_after(&next)?;
+            if next > now {
+                return Some(next.with_timezone(&Utc));
+            }
+        }
+    }
+
+    ///Human readable rule, "every 2 weeks on mon, fri"
+    pub fn describe(&self) -> String {
+        let unit = match self.frequency {
+            Frequency::Daily => "day",
+            Frequency::Weekly => "week",
+            Frequency::Monthly => "month",
+        };
+        let mut description = match self.interval {
+            1 => format!("every {}", unit),
+            interval => format!("every {} {}s", interval, unit),
+        };
+        if !self.weekdays.is_empty() {
+            let weekdays: Vec<String> = self
+                .weekdays
+                .iter()
+                .map(|x| x.to_string().to_lowercase())
+                .collect();
+            description.push_str(&format!(" on {}", weekdays.join(", ")));
+        }
+        if let Some(day) = self.month_day {
+            description.push_str(&format!(" on day {}", day));
+        }
+        description
+    }
+
+    fn validate(self) -> Result<Recurrence> {
+        if self.interval == 0 {
+            return Err(Error::Validation("Interval must be at least 1".to_string()));
+        }
+        if !self.weekdays.is_empty() && self.frequency != Frequency::Weekly {
+            return Err(Error::Validation(
+                "Weekdays are only allowed in weekly rules".to_string(),
+            ));
+        }
+        match self.month_day {
+            Some(_) if self.frequency != Frequency::Monthly => Err(Error::Validation(
+                "Day of month is only allowed in monthly rules".to_string(),
+            )),
+            Some(day) if !(1..=31).contains(&day) => {
+                Err(Error::Validation(format!("Invalid day of month {}", day)))
+            }
+            _ => Ok(self),
+        }
+    }
+}
+
+fn week_start(date: NaiveDate) -> NaiveDate {
+    date - Duration::days(date.weekday().num_days_from_monday().into())
+}
+
+///Day `day` of the month of `date`, or its last day in shorter months
+fn with_month_day(date: NaiveDate, day: u32) -> Option<NaiveDate> {
+    (28..=day.max(28))
+        .rev()
+        .find_map(|x| date.with_day(x.min(day)))
+}
+
+///RRULE text, the stored form
+impl fmt::Display for Recurrence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let frequency = match self.frequency {
+            Frequency::Daily => "DAILY",
+            Frequency::Weekly => "WEEKLY",
+            Frequency::Monthly => "MONTHLY",
+        };
+        write!(f, "FREQ={}", frequency)?;
+        if self.interval != 1 {
+            write!(f, ";INTERVAL={}", self.interval)?;
+        }
+        if !self.weekdays.is_empty() {
+            let weekdays: Vec<String> = self
+                .weekdays
+                .iter()
+                .map(|x| x.to_string()[..2].to_uppercase())
+                .collect();
+            write!(f, ";BYDAY={}", weekdays.join(","))?;
+        }
+        if let Some(day) = self.month_day {
+            write!(f, ";BYMONTHDAY={}", day)?;
+        }
+        Ok(())
+    }
+}
+
+///RRULE text or a phrase like "every 2 weeks on mon,fri"
+impl FromStr for Recurrence {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<Recurrence> {
+        let invalid = || Error::Validation(format!("Invalid recurrence {}", text));
+        let recurrence = match text.trim().to_uppercase().starts_with("FREQ=") {
+            true => parse_rrule(text.trim()),
+            false => parse_phrase(text),
+        };
+        recurrence.ok_or_else(invalid)?.validate()
+    }
+}
+
+fn parse_rrule(text: &str) -> Option<Recurrence> {
+    let mut recurrence = Recurrence::new(Frequency::Daily, 1);
+    for part in text.to_uppercase().split(';').filter(|x| !x.is_empty()) {
+        let (key, value) = part.split_once('=')?;
+        match key {
+            "FREQ" => recurrence.frequency = parse_frequency(value)?,
+            "INTERVAL" => recurrence.interval = value.parse().ok()?,
+            "BYDAY" => {
+                recurrence.weekdays = value.split(',').map(parse_weekday).collect::<Option<_>>()?
+            }
+            "BYMONTHDAY" => recurrence.month_day = Some(value.parse().ok()?),
+            _ => return None,
+        }
+    }
+    Some(recurrence)
+}
+
+fn parse_phrase(text: &str) -> Option<Recurrence> {
+    let text = text.to_lowercase().replace(',', " ");
+    let words: Vec<&str> = text.split_whitespace().collect();
+
+    let (mut recurrence, rest) = match words.as_slice() {
+        [word, rest @ ..] if parse_frequency(word).is_some() => {
+            (Recurrence::new(parse_frequency(word)?, 1), rest)
+        }
+        ["every", amount, unit, rest @ ..] if amount.parse::<u32>().is_ok() => (
+            Recurrence::new(parse_unit(unit)?, amount.parse().ok()?),
+            rest,
+        ),
+        ["every", unit, rest @ ..] if parse_unit(unit).is_some() => {
+            (Recurrence::new(parse_unit(unit)?, 1), rest)
+        }
+        //"every monday and thursday"
+        ["every", rest @ ..] if !rest.is_empty() => (Recurrence::new(Frequency::Weekly, 1), rest),
+        _ => return None,
+    };
+
+    let rest = match rest {
+        ["on", rest @ ..] => rest,
+        _ => rest,
+    };
+    let items: Vec<&str> = rest.iter().copied().filter(|x| *x != "and").collect();
+    if items.is_empty() {
+        return Some(recurrence);
+    }
+    match recurrence.frequency {
+        Frequency::Weekly => {
+            recurrence.weekdays = items
+                .iter()
+                .map(|x| x.parse().ok())
+                .collect::<Option<_>>()?
+        }
+        Frequency::Monthly => match items.as_slice() {
+            ["day", day] | [day] => {
+                let day = day.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+                recurrence.month_day = Some(day.parse().ok()?)
+            }
+            _ => return None,
+        },
+        Frequency::Daily => return None,
+    }
+    Some(recurrence)
+}
+
+fn parse_frequency(text: &str) -> Option<Frequency> {
+    match text.to_lowercase().as_str() {
+        "daily" => Some(Frequency::Daily),
+        "weekly" => Some(Frequency::Weekly),
+        "monthly" => Some(Frequency::Monthly),
+        _ => None,
+    }
+}
+
+fn parse_unit(text: &str) -> Option<Frequency> {
+    match text.trim_end_matches('s') {
+        "day" => Some(Frequency::Daily),
+        "week" => Some(Frequency::Weekly),
+        "month" => Some(Frequency::Monthly),
+        _ => None,
+    }
+}
+
+///Two letter RRULE weekday, MO to SU
+fn parse_weekday(text: &str) -> Option<Weekday> {
+    let weekday = match text {
+        "MO" => Weekday::Mon,
+        "TU" => Weekday::Tue,
+        "WE" => Weekday::Wed,
+        "TH" => Weekday::Thu,
+        "FR" => Weekday::Fri,
+        "SA" => Weekday::Sat,
+        "SU" => Weekday::Sun,
+        _ => return None,
+    };
+    Some(weekday)
+}
+
+impl ToSql for Recurrence {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.to_string()))
+    }
+}
+
+impl FromSql for Recurrence {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        value
+            .as_str()?
+            .parse()
+            .map_err(|x| FromSqlError::Other(Box::new(x)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //Wednesday
+    const NOW: &str = "2026-10-14 09:00:00";
+
+    fn at(text: &str) -> DateTime<Utc> {
+        Utc.datetime_from_str(text, "%Y-%m-%d %H:%M:%S").unwrap()
+    }
+
+    fn rule(text: &str) -> Recurrence {
+        text.parse().unwrap()
+    }
+
+    fn occurrences(text: &str, start: &str, count: usize) -> Vec<String> {
+        let rule = rule(text);
+        let mut next = at(start);
+        (0..count)
+            .map(|_| {
+                next = rule.next_after(&next).unwrap();
+                next.format("%Y-%m-%d %a").to_string()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn phrases_and_rrules_are_equivalent() {
+        for (phrase, rrule) in [
+            ("daily", "FREQ=DAILY"),
+            ("every 3 days", "FREQ=DAILY;INTERVAL=3"),
+            ("weekly on mon, fri", "FREQ=WEEKLY;BYDAY=MO,FR"),
+            (
+                "every 2 weeks on tuesday",
+                "FREQ=WEEKLY;INTERVAL=2;BYDAY=TU",
+            ),
+            ("every monday and thursday", "FREQ=WEEKLY;BYDAY=MO,TH"),
+            ("monthly on 15th", "FREQ=MONTHLY;BYMONTHDAY=15"),
+            ("every month on day 31", "FREQ=MONTHLY;BYMONTHDAY=31"),
+        ] {
+            assert_eq!(rule(phrase).to_string(), rrule, "{}", phrase);
+            assert_eq!(rule(rrule), rule(phrase), "{}", rrule);
+        }
+    }
+
+    #[test]
+    fn invalid_rules_are_rejected() {
+        for text in [
+            "",
+            "every",
+            "sometimes",
+            "every 0 days",
+            "daily on monday",
+            "monthly on 32",
+            "FREQ=YEARLY",
+            "FREQ=DAILY;BYDAY=MO",
+            "FREQ=WEEKLY;COUNT=3",
+        ] {
+            assert!(text.parse::<Recurrence>().is_err(), "{}", text);
+        }
+    }
+
+    #[test]
+    fn daily_and_weekly_occurrences() {
+        assert_eq!(
+            occurrences("every 3 days", NOW, 2),
+            ["2026-10-17 Sat", "2026-10-20 Tue"]
+        );
+        assert_eq!(
+            occurrences("weekly", NOW, 2),
+            ["2026-10-21 Wed", "2026-10-28 Wed"]
+        );
+        assert_eq!(
+            occurrences("weekly on mon,wed,fri", NOW, 3),
+            ["2026-10-16 Fri", "2026-10-19 Mon", "2026-10-21 Wed"]
+        );
+        assert_eq!(
+            occurrences("every 2 weeks on mon,thu", NOW, 3),
+            ["2026-10-15 Thu", "2026-10-26 Mon", "2026-10-29 Thu"]
+        );
+    }
+
+    #[test]
+    fn anchored_monthly_series_keeps_its_day() {
+        let start = at("2026-01-31 09:00:00");
+        let monthly = rule("monthly").anchored(&start);
+        assert_eq!(monthly.to_string(), "FREQ=MONTHLY;BYMONTHDAY=31");
+        assert_eq!(
+            occurrences(&monthly.to_string(), "2026-01-31 09:00:00", 2),
+            ["2026-02-28 Sat", "2026-03-31 Tue"]
+        );
+        assert_eq!(rule("weekly").anchored(&start), rule("weekly"));
+    }
+
+    #[test]
+    fn monthly_occurrences_are_clamped() {
+        assert_eq!(
+            occurrences("monthly on 31", NOW, 3),
+            ["2026-10-31 Sat", "2026-11-30 Mon", "2026-12-31 Thu"]
+        );
+        assert_eq!(occurrences("monthly on 2", NOW, 1), ["2026-11-02 Mon"]);
+        assert_eq!(
+            occurrences("every 2 months", "2026-01-31 09:00:00", 2),
+            ["2026-03-31 Tue", "2026-05-31 Sun"]
+        );
+    }
+
+    #[test]
+    fn next_after_keeps_the_time_of_day() {
+        let next = rule("daily").next_after(&at(NOW)).unwrap();
+        assert_eq!(next, at("2026-10-15 09:00:00"));
+    }
+
+    #[test]
+    fn missed_occurrences_are_skipped() {
+        let next = rule("weekly")
+            .next_occurrence(at(NOW), at("2026-10-30 12:00:00"))
+            .unwrap();
+        assert!(next > at("2026-10-30 12:00:00"));
+        assert!(next <= at("2026-11-05 12:00:00"));
+    }
+}
@@ -0,0 +1,261 @@
+use crate::{
+    dates::format_datetime,
+    error::Result,
+    store::{TaskFilter, TaskSort, TaskStore},
+    Board, Comment, Priority, Task,
+};
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use std::fmt::Write;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ReportFormat {
+    #[default]
+    Markdown,
+    ///Standalone page
+    Html,
+}
+
+#[derive(Debug)]
+pub struct ReportTask {
+    pub task: Task,
+    pub comments: Vec<Comment>,
+}
+
+#[derive(Debug)]
+pub struct BoardReport {
+    pub board: Board,
+    pub pending: Vec<ReportTask>,
+    pub done: Vec<ReportTask>,
+}
+
+///Pending tasks created and done tasks completed in a date range, grouped by board
+#[derive(Debug)]
+pub struct Report {
+    pub generated_at: DateTime<Utc>,
+    ///Inclusive
+    pub from: Option<DateTime<Utc>>,
+    ///Exclusive
+    pub to: Option<DateTime<Utc>>,
+    pub boards: Vec<BoardReport>,
+}
+
+impl ReportTask {
+    fn overdue(&self, now: &DateTime<Utc>) -> bool {
+        self.task.done == 0 && self.task.due_at.is_some_and(|x| x < *now)
+    }
+}
+
+///Every board unless some are given, most urgent tasks first
+pub fn build(
+    store: &dyn TaskStore,
+    board_ids: &[u16],
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+) -> Result<Report> {
+    let mut boards = Vec::new();
+    for board in store.list_boards()? {
+        if !board_ids.is_empty() && !board_ids.contains(&board.id) {
+            continue;
+        }
+        let pending = TaskFilter {
+            created_after: from,
+            created_before: to,
+            sort: TaskSort::Priority,
+            ..TaskFilter::pending().board(board.id)
+        };
+        let done = TaskFilter {
+            completed_after: from,
+            completed_before: to,
+            sort: TaskSort::Priority,
+            ..TaskFilter::done(true).board(board.id)
+        };
+        boards.push(BoardReport {
+            board,
+            pending: report_tasks(store, &pending)?,
+            done: report_tasks(store, &done)?,
+        });
+    }
+    Ok(Report {
+        generated_at: Utc::now(),
+        from,
+        to,
+        boards,
+    })
+}
+
+fn report_tasks(store: &dyn TaskStore, filter: &TaskFilter) -> Result<Vec<ReportTask>> {
+    let mut tasks = Vec::new();
+    for task in store.list_tasks(filter)? {
+        let comments = store.list_comments(task.id)?;
+        tasks.push(ReportTask { task, comments });
+    }
+    Ok(tasks)
+}
+
+fn describe_range(report: &Report) -> String {
+    match (report.from, report.to) {
+        (Some(from), Some(to)) => format!(
+            "Tasks created or completed from {} to {}",
+            format_datetime(&from),
+            format_datetime(&to)
+        ),
+        (Some(from), None) => format!(
+            "Tasks created or completed since {}",
+            format_datetime(&from)
+        ),
+        (None, Some(to)) => format!("Tasks created or completed before {}", format_datetime(&to)),
+        (None, None) => "All tasks".to_string(),
+    }
+}
+
+///"#3, urgent, due Mon, ..." leaving out the normal priority. Tags, the only free text, are
+///escaped for the output format
+fn describe_task(task: &Task, escape: fn(&str) -> String) -> String {
+    let mut details = vec![format!("#{}", task.id)];
+    if task.priority != Priority::Normal {
+        details.push(task.priority.to_string());
+    }
+    if let Some(due_at) = &task.due_at {
+        details.push(format!("due {}", format_datetime(due_at)));
+    }
+    if !task.tags.is_empty() {
+        details.push(escape(&task.tags.join(", ")));
+    }
+    details.join(", ")
+}
+
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::new();
+    for x in text.chars() {
+        if "\\`*_[]<>#|~".contains(x) {
+            escaped.push('\\');
+        }
+        escaped.push(x);
+    }
+    escaped
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+///Overdue tasks are in bold, comments are nested bullets
+pub fn to_markdown(report: &Report) -> String {
+    let mut text = String::new();
+    let _ = writeln!(text, "# Task report\n");
+    let _ = writeln!(
+        text,
+        "{}, generated {}.",
+        describe_range(report),
+        format_datetime(&report.generated_at)
+    );
+
+    for board in &report.boards {
+        let _ = writeln!(text, "\n## {}", escape_markdown(&board.board.title));
+        let sections = [("Pending", &board.pending), ("Done", &board.done)];
+        for (title, tasks) in sections {
+            let _ = writeln!(text, "\n### {} ({})\n", title, tasks.len());
+            if tasks.is_empty() {
+                let _ = writeln!(text, "_No tasks_");
+            }
+            for item in tasks {
+                let title = escape_markdown(&item.task.title);
+                let details = describe_task(&item.task, escape_markdown);
+                let _ = match item.overdue(&report.generated_at) {
+                    true => writeln!(text, "- **{} (overdue)** {}", title, details),
+                    false => writeln!(text, "- {} {}", title, details),
+                };
+                for comment in &item.comments {
+                    let _ = writeln!(
+                        text,
+                        "  - {} _{}_",
+                        escape_markdown(&comment.title),
+                        format_datetime(&comment.created_at)
+                    );
+                }
+            }
+        }
+    }
+    text
+}
+
+pub fn to_html(report: &Report) -> String {
+    let mut html = String::new();
+    let _ = writeln!(
+        html,
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Task report</title>\n\
+        <style>.overdue {{ color: #c00; font-weight: bold; }} .details, .date {{ color: #666; }}</style>\n\
+        </head>\n<body>\n<h1>Task report</h1>"
+    );
+    let _ = writeln!(
+        html,
+        "<p>{}, generated {}.</p>",
+        describe_range(report),
+        format_datetime(&report.generated_at)
+    );
+
+    for board in &report.boards {
+        let _ = writeln!(html, "<h2>{}</h2>", escape_html(&board.board.title));
+        let sections = [("Pending", &board.pending), ("Done", &board.done)];
+        for (title, tasks) in sections {
+            let _ = writeln!(html, "<h3>{} ({})</h3>", title, tasks.len());
+            if tasks.is_empty() {
+                let _ = writeln!(html, "<p><em>No tasks</em></p>");
+                continue;
+            }
+            let _ = writeln!(html, "<ul>");
+            for item in tasks {
+                let title = match item.overdue(&report.generated_at) {
+                    true => format!(
+                        "<span class=\"overdue\">{} (overdue)</span>",
+                        escape_html(&item.task.title)
+                    ),
+                    false => escape_html(&item.task.title),
+                };
+                let _ = write!(
+                    html,
+                    "<li>{} <span class=\"details\">{}</span>",
+                    title,
+                    describe_task(&item.task, escape_html)
+                );
+                if !item.comments.is_empty() {
+                    let _ = writeln!(html, "\n<ul>");
+                    for comment in &item.comments {
+                        let _ = writeln!(
+                            html,
+                            "<li>{} <span class=\"date\">{}</span></li>",
+                            escape_html(&comment.title),
+                            format_datetime(&comment.created_at)
+                        );
+                    }
+                    let _ = writeln!(html, "</ul>");
+                }
+                let _ = writeln!(html, "</li>");
+            }
+            let _ = writeln!(html, "</ul>");
+        }
+    }
+    let _ = writeln!(html, "</body>\n</html>");
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes() {
+        assert_eq!(
+            escape_markdown("fix *all* [bugs]"),
+            "fix \\*all\\* \\[bugs\\]"
+        );
+        assert_eq!(
+            escape_html("<b>\"R&D\"</b>"),
+            "&lt;b&gt;&quot;R&amp;D&quot;&lt;/b&gt;"
+        );
+    }
+}
@@ -0,0 +1,73 @@
+use crate::Task;
+use colored::*;
+
+pub const HIGHLIGHT_START: &str = "\u{2}";
+pub const HIGHLIGHT_END: &str = "\u{3}";
+pub const SNIPPET_ELLIPSIS: &str = "…";
+///Tokens shown around a match in comment snippets
+pub const SNIPPET_TOKENS: u8 = 8;
+pub const DEFAULT_SEARCH_LIMIT: u32 = 20;
+
+///Task matching a search, best match first
+#[derive(Debug)]
+pub struct SearchResult {
+    pub task: Task,
+    ///bm25 score, lower is better
+    pub rank: f64,
+    ///Title with matches between HIGHLIGHT_START and HIGHLIGHT_END
+    pub title: String,
+    ///Snippets of the matching comments, highlighted like the title
+    pub comments: Vec<String>,
+}
+
+///Turn free text into an FTS5 query matching every word as a prefix, so user input can't break the syntax
+pub fn to_fts_query(text: &str) -> Option<String> {
+    let terms: Vec<String> = text
+        .split_whitespace()
+        .map(|x| format!("\"{}\"*", x.replace('"', "\"\"")))
+        .collect();
+
+    match terms.is_empty() {
+        true => None,
+        false => Some(terms.join(" ")),
+    }
+}
+
+///Replace highlight markers with terminal colors
+pub fn render_highlight(text: &str) -> String {
+    let mut rendered = String::new();
+
+    for (i, part) in text.split(HIGHLIGHT_START).enumerate() {
+        if i == 0 {
+            rendered.push_str(part);
+            continue;
+        }
+        match part.split_once(HIGHLIGHT_END) {
+            Some((matched, rest)) => {
+                rendered.push_str(&matched.yellow().bold().to_string());
+                rendered.push_str(rest);
+            }
+            None => rendered.push_str(part),
+        }
+    }
+    rendered
+}
+
+pub fn display_search_results(results: &[SearchResult]) {
+    for result in results {
+        let status = match result.task.done {
+            1 => "done",
+            _ => "pending",
+        };
+        println!(
+            "{} - {} [board {}, {}]",
+            result.task.id,
+            render_highlight(&result.title),
+            result.task.board_id,
+            status
+        );
+        for comment in &result.comments {
+            println!("    ↳ {}", render_highlight(comment));
+        }
+    }
+}
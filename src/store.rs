@@ -0,0 +1,132 @@
+pub use crate::filter::{TaskFilter, TaskSort};
+use crate::{
+    dates::ReminderTime, error::Result, history::HistoryEntry, journal::Operation,
+    recurrence::Recurrence, search::SearchResult, tags::Tag, Board, Column, Comment, Orphan,
+    Priority, Reminder, Task,
+};
+use chrono::{DateTime, Utc};
+
+///Fields needed to create a task
+#[derive(Debug, Default, Clone)]
+pub struct NewTask {
+    pub title: String,
+    pub board_id: u16,
+    pub priority: Priority,
+    pub due_at: Option<DateTime<Utc>>,
+    pub recurrence: Option<Recurrence>,
+    pub parent_id: Option<u16>,
+    ///None for the first column of the board
+    pub column_id: Option<u16>,
+    ///None for now
+    pub created_at: Option<DateTime<Utc>>,
+    ///Only kept when the task is created in the terminal column, None for now
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+impl NewTask {
+    pub fn new(title: &str, board_id: u16) -> NewTask {
+        NewTask {
+            title: title.to_string(),
+            board_id,
+            ..Default::default()
+        }
+    }
+}
+
+///Persistence of boards, tasks and comments, free of any user interaction
+pub trait TaskStore {
+    fn count_records(&self, table: &str) -> Result<u16>;
+    ///Run `f` as a single unit, its changes being rolled back when it fails or when `commit`
+    ///is false
+    fn transaction(&self, commit: bool, f: &mut dyn FnMut() -> Result<()>) -> Result<()>;
+    ///Record the changes made by `f` as one operation, undone and redone as a whole. Calls
+    ///made while recording join the current operation, and nothing is kept when `f` changes
+    ///nothing. Recording a change forgets the undone operations
+    fn journaled(&self, description: &str, f: &mut dyn FnMut() -> Result<()>) -> Result<()>;
+    ///Revert the latest operation still applied, None when there is nothing to undo
+    fn undo(&self) -> Result<Option<Operation>>;
+    ///Apply again the earliest undone operation, None when there is nothing to redo
+    fn redo(&self) -> Result<Option<Operation>>;
+    ///Latest operations first, undone ones included
+    fn list_operations(&self, limit: u32) -> Result<Vec<Operation>>;
+
+    ///Creates the default columns of the board
+    fn add_board(&self, title: &str) -> Result<u16>;
+    fn get_board(&self, id: u16) -> Result<Board>;
+    fn list_boards(&self) -> Result<Vec<Board>>;
+    fn rename_board(&self, id: u16, title: &str) -> Result<()>;
+    ///Deletes the board's tasks and their comments too
+    fn delete_board(&self, id: u16) -> Result<()>;
+
+    ///Added as the last column before the terminal one
+    fn add_column(&self, board_id: u16, title: &str) -> Result<u16>;
+    fn get_column(&self, id: u16) -> Result<Column>;
+    ///Columns of the board, in order
+    fn list_columns(&self, board_id: u16) -> Result<Vec<Column>>;
+    fn rename_column(&self, id: u16, title: &str) -> Result<()>;
+    ///Move the column to this position, shifting the others
+    fn move_column(&self, id: u16, position: u16) -> Result<()>;
+    ///Make it the terminal column of its board, the previous one becoming a regular column.
+    ///Tasks of both columns change their done flag accordingly
+    fn set_terminal_column(&self, id: u16) -> Result<()>;
+    ///Its tasks go to the first column of the board. The terminal column can't be deleted,
+    ///nor the last regular one
+    fn delete_column(&self, id: u16) -> Result<()>;
+
+    ///The parent task must exist
+    fn add_task(&self, task: &NewTask) -> Result<u16>;
+    fn get_task(&self, id: u16) -> Result<Task>;
+    fn list_tasks(&self, filter: &TaskFilter) -> Result<Vec<Task>>;
+    ///Saves every editable field of the task and records the changes in its history.
+    ///Reminders relative to the due date follow it, and are removed with it. A task can't
+    ///become a subtask of itself or of its subtasks. Changing the column sets the done flag
+    ///and the completion time, while changing the done flag or the board moves the task to
+    ///the first matching column
+    fn update_task(&self, task: &Task) -> Result<()>;
+    ///Move every task matching the filter to the first matching column of the board,
+    ///returning how many moved
+    fn move_tasks(&self, filter: &TaskFilter, board_id: u16) -> Result<usize>;
+    ///Deletes the task's subtasks, comments, reminders, dependencies and tag links too
+    fn delete_task(&self, id: u16) -> Result<()>;
+
+    ///Changes of the task, oldest first. Kept after the task is deleted
+    fn list_history(&self, task_id: u16) -> Result<Vec<HistoryEntry>>;
+
+    fn add_comment(&self, task_id: u16, title: &str) -> Result<u16>;
+    fn list_comments(&self, task_id: u16) -> Result<Vec<Comment>>;
+
+    ///Replace the tags of the task, creating the missing ones
+    fn set_task_tags(&self, task_id: u16, tags: &[String]) -> Result<()>;
+    ///Every tag with its number of tasks, by name
+    fn list_tags(&self) -> Result<Vec<Tag>>;
+    ///Case insensitive
+    fn get_tag(&self, name: &str) -> Result<Tag>;
+    ///Refuses a name already used by another tag, merge them instead
+    fn rename_tag(&self, id: u16, name: &str) -> Result<()>;
+    ///Move the tasks of `from_id` to `into_id` and delete `from_id`
+    fn merge_tags(&self, from_id: u16, into_id: u16) -> Result<()>;
+    ///Untags its tasks
+    fn delete_tag(&self, id: u16) -> Result<()>;
+
+    ///Make the blocker a prerequisite of the task, refusing cycles
+    fn add_dependency(&self, task_id: u16, blocker_id: u16) -> Result<()>;
+    fn remove_dependency(&self, task_id: u16, blocker_id: u16) -> Result<()>;
+    ///Tasks the task depends on, done ones included
+    fn list_blockers(&self, task_id: u16) -> Result<Vec<Task>>;
+
+    ///Reminders before the due date need the task to have one
+    fn add_reminder(&self, task_id: u16, time: ReminderTime) -> Result<u16>;
+    fn list_reminders(&self, task_id: u16) -> Result<Vec<Reminder>>;
+    ///Not fired reminders of pending tasks at or before `now`, oldest first
+    fn list_due_reminders(&self, now: DateTime<Utc>) -> Result<Vec<Reminder>>;
+    fn update_reminder(&self, reminder: &Reminder) -> Result<()>;
+    fn delete_reminder(&self, id: u16) -> Result<()>;
+
+    ///Tasks whose title or comments match every word of the text, best match first
+    fn search(&self, text: &str, limit: u32) -> Result<Vec<SearchResult>>;
+
+    ///Rows referencing a missing board or task
+    fn check_integrity(&self) -> Result<Vec<Orphan>>;
+    ///Delete orphan rows, cascading to their own children. Returns how many were found
+    fn repair_orphans(&self) -> Result<usize>;
+}
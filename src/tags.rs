@@ -0,0 +1,95 @@
+use crate::error::{Error, Result};
+use std::{fmt, str::FromStr};
+use tabled::Tabled;
+
+///Shown to the user when asking for a tag expression
+pub const TAG_EXAMPLES: &str = "+work -blocked";
+
+///Free-form label of a task, a task having any number of them
+#[derive(Debug, Clone, PartialEq, Eq, Tabled)]
+pub struct Tag {
+    pub id: u16,
+    pub name: String,
+    ///Read only, number of tagged tasks
+    pub tasks: u16,
+}
+
+///Tag names are single words, compared case insensitively
+pub fn validate_tag_name(name: &str) -> Result<String> {
+    let name = name.trim();
+    let invalid = name.is_empty()
+        || name.starts_with(['+', '-'])
+        || name.contains(|x: char| x.is_whitespace() || x == ',');
+    match invalid {
+        true => Err(Error::Validation(format!(
+            "Invalid tag '{}', use a single word without commas",
+            name
+        ))),
+        false => Ok(name.to_string()),
+    }
+}
+
+///Tasks having every `include` tag and none of the `exclude` ones, parsed from "+work -blocked".
+///A bare word counts as included
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct TagFilter {
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+}
+
+impl TagFilter {
+    pub fn is_empty(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_empty()
+    }
+}
+
+impl FromStr for TagFilter {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<TagFilter> {
+        let mut filter = TagFilter::default();
+        for word in text.split_whitespace() {
+            match (word.strip_prefix('-'), word.strip_prefix('+')) {
+                (Some(name), _) => filter.exclude.push(validate_tag_name(name)?),
+                (_, Some(name)) => filter.include.push(validate_tag_name(name)?),
+                _ => filter.include.push(validate_tag_name(word)?),
+            }
+        }
+        Ok(filter)
+    }
+}
+
+impl fmt::Display for TagFilter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let words: Vec<String> = self
+            .include
+            .iter()
+            .map(|x| format!("+{}", x))
+            .chain(self.exclude.iter().map(|x| format!("-{}", x)))
+            .collect();
+        write!(f, "{}", words.join(" "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expressions() {
+        let filter: TagFilter = "+work -blocked home".parse().unwrap();
+        assert_eq!(filter.include, ["work", "home"]);
+        assert_eq!(filter.exclude, ["blocked"]);
+        assert_eq!(filter.to_string(), "+work +home -blocked");
+        assert!("".parse::<TagFilter>().unwrap().is_empty());
+    }
+
+    #[test]
+    fn invalid_names() {
+        for text in ["+", "-", "+-work", "a,b", "--x"] {
+            assert!(text.parse::<TagFilter>().is_err(), "{}", text);
+        }
+        assert!(validate_tag_name("two words").is_err());
+        assert_eq!(validate_tag_name(" work ").unwrap(), "work");
+    }
+}
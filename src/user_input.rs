@@ -1,104 +1,103 @@
 use crate::{
-    display_message, Color, ALTERNATIVE_DATETIME_FORMAT, DATETIME_FORMAT, DATE_FORMAT, TIME_FORMAT,
+    dates::{parse_datetime, DATE_EXAMPLES},
+    error::{Error, Result},
 };
-use chrono::{TimeZone, Utc};
+use chrono::{DateTime, SubsecRound, Utc};
 use dialoguer::{theme::ColorfulTheme, Confirm, Input, Select};
 use std::fmt::Display;
 
 ///Get boolean response
-pub fn get_user_confirmation(question: &str) -> bool {
+pub fn get_user_confirmation(question: &str) -> Result<bool> {
     Confirm::with_theme(&ColorfulTheme::default())
         .with_prompt(question)
         .default(true)
-        .interact()
-        .unwrap()
+        .interact_opt()?
+        .ok_or(Error::InputCancelled)
 }
 
 ///Get text response.
-pub fn get_user_input(text: &str, default_text: &str, allow_spaces: bool) -> Option<String> {
+pub fn get_user_input(text: &str, default_text: &str, allow_spaces: bool) -> Result<String> {
     let res: String = Input::with_theme(&ColorfulTheme::default())
         .with_prompt(text)
         .default(default_text.into())
-        .interact_text()
-        .unwrap();
+        .interact_text()?;
 
     if allow_spaces {
-        return Some(res);
+        return Ok(res);
     }
 
     let text_parts = &res.split_ascii_whitespace().count();
     if text_parts != &1_usize {
-        display_message("error", "Spaces are not allowed", crate::Color::Red);
-        return None;
+        return Err(Error::Validation("Spaces are not allowed".to_string()));
     }
     let res = res.split_ascii_whitespace().next().unwrap().to_string();
-    Some(res)
+    Ok(res)
 }
 
 //Get singe response from choices
-pub fn get_user_selection<T>(items: &Vec<T>, title: &str) -> (String, usize)
+pub fn get_user_selection<T>(items: &[T], title: &str) -> Result<(String, usize)>
 where
     T: Display,
 {
-    let selection = Select::with_theme(&ColorfulTheme::default())
-        .items(&items)
-        .with_prompt(title)
-        .default(0)
-        .interact()
-        .unwrap();
-
-    (items.get(selection).unwrap().to_string(), selection)
+    let selection = get_user_selection_index(items, title, 0)?;
+    Ok((items[selection].to_string(), selection))
 }
 
-//Get singe response from choices
-pub fn get_user_selection_text<T>(items: &Vec<T>, title: &str) -> String
+//Get index of singe response from choices, starting at the default one
+pub fn get_user_selection_index<T>(items: &[T], title: &str, default: usize) -> Result<usize>
 where
     T: Display,
 {
-    let selection = Select::with_theme(&ColorfulTheme::default())
-        .items(&items)
+    Select::with_theme(&ColorfulTheme::default())
+        .items(items)
         .with_prompt(title)
-        .default(0)
-        .interact()
-        .unwrap();
+        .default(default)
+        .interact_opt()?
+        .ok_or(Error::InputCancelled)
+}
 
-    items[selection].to_string()
+//Get singe response from choices
+pub fn get_user_selection_text<T>(items: &[T], title: &str) -> Result<String>
+where
+    T: Display,
+{
+    let (selection, _) = get_user_selection(items, title)?;
+    Ok(selection)
 }
 
 //Get date response
-pub fn get_user_date(midnight: bool, must_be_future: bool) -> Option<String> {
-    let now = Utc::now();
-    let today_str = now.format(DATE_FORMAT).to_string();
-    let midnight_time = "00:00:00";
-
-    let current_time_str = match midnight {
-        true => midnight_time.to_string(),
-        false => now.format(TIME_FORMAT).to_string(),
-    };
+pub fn get_user_date(must_be_future: bool) -> Result<DateTime<Utc>> {
+    let date = get_user_input(
+        format!("When (e.g. {})", DATE_EXAMPLES).as_str(),
+        "tomorrow 9am",
+        true,
+    )?;
+    parse_user_date(&date, must_be_future)
+}
 
-    let datetime_str = format!(
-        "{} {}",
-        get_user_input(
-            format!("Date ({})", DATE_FORMAT).as_str(),
-            &today_str,
-            false
-        )
-        .unwrap(),
-        current_time_str
-    );
-    let datetime_date = Utc.datetime_from_str(&datetime_str, &ALTERNATIVE_DATETIME_FORMAT);
+//Parse a date for filters, see the dates module for the accepted formats
+pub fn parse_date(date: &str) -> Result<DateTime<Utc>> {
+    parse_datetime(date)
+}
 
-    if datetime_date.is_err() {
-        display_message("error", "Invalid date", Color::Red);
-        return None;
+//Parse a date, rejecting past ones when asked
+pub fn parse_user_date(date: &str, must_be_future: bool) -> Result<DateTime<Utc>> {
+    //Taken first and truncated like the parsed date, so "now" isn't in the past
+    let now = Utc::now().trunc_subsecs(0);
+    let datetime = parse_datetime(date)?;
+    if must_be_future && now > datetime {
+        return Err(Error::Validation("Datetime cannot be past".to_string()));
     }
+    Ok(datetime)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    if must_be_future {
-        if now > datetime_date.unwrap() {
-            display_message("error", "Datetime cannot be past", Color::Red);
-            return None;
-        }
+    #[test]
+    fn now_is_not_past() {
+        assert!(parse_user_date("now", true).is_ok());
+        assert!(parse_user_date("2000-01-01", true).is_err());
     }
-    let datetime_str = datetime_date.unwrap().format(DATETIME_FORMAT).to_string();
-    Some(datetime_str)
 }
@@ -0,0 +1,99 @@
+use task_manager::{
+    dao::SqliteStore,
+    dates::ReminderTime,
+    export::{collect, write_csv, write_json, Export, EXPORT_VERSION},
+    store::{NewTask, TaskFilter, TaskStore},
+    Priority,
+};
+
+fn sample_store() -> SqliteStore {
+    let store = SqliteStore::open_in_memory().unwrap();
+    let work = store.add_board("work").unwrap();
+    let home = store.add_board("home").unwrap();
+    let report = store
+        .add_task(&NewTask {
+            priority: Priority::High,
+            recurrence: Some("every week".parse().unwrap()),
+            ..NewTask::new("report", work)
+        })
+        .unwrap();
+    let review = store
+        .add_task(&NewTask::new("review, \"final\"", work))
+        .unwrap();
+    store.add_task(&NewTask::new("milk", home)).unwrap();
+    store.add_comment(report, "draft sent").unwrap();
+    store.add_dependency(review, report).unwrap();
+    store.set_task_tags(report, &["q3".to_string()]).unwrap();
+    store
+        .add_reminder(report, ReminderTime::At(chrono::Utc::now()))
+        .unwrap();
+    store
+}
+
+#[test]
+fn json_nests_every_field_by_board() {
+    let store = sample_store();
+    let export = collect(&store, &TaskFilter::default()).unwrap();
+    let mut json = Vec::new();
+    write_json(&export, &mut json).unwrap();
+
+    let parsed: Export = serde_json::from_slice(&json).unwrap();
+    assert_eq!(parsed.version, EXPORT_VERSION);
+    assert_eq!(parsed.boards.len(), 2);
+    let work = &parsed.boards[0];
+    assert_eq!(work.columns.len(), 3);
+    let report = work.tasks.iter().find(|x| x.title == "report").unwrap();
+    assert_eq!(report.priority, Priority::High);
+    assert_eq!(report.recurrence.as_deref(), Some("FREQ=WEEKLY"));
+    assert_eq!(report.tags, ["q3"]);
+    assert_eq!(report.comments[0].title, "draft sent");
+    assert_eq!(report.reminders.len(), 1);
+    let review = work.tasks.iter().find(|x| x.id == 2).unwrap();
+    assert_eq!(review.blocked_by, [report.id]);
+    assert!(String::from_utf8(json)
+        .unwrap()
+        .contains("\"priority\": \"high\""));
+}
+
+#[test]
+fn filter_limits_boards_and_tasks() {
+    let store = sample_store();
+    let filter = TaskFilter {
+        text: Some("milk".to_string()),
+        ..TaskFilter::default().board(2)
+    };
+    let export = collect(&store, &filter).unwrap();
+    assert_eq!(export.boards.len(), 1);
+    assert_eq!(export.boards[0].title, "home");
+    assert_eq!(export.boards[0].tasks.len(), 1);
+
+    let filter = TaskFilter {
+        text: Some("nothing".to_string()),
+        ..TaskFilter::default()
+    };
+    let export = collect(&store, &filter).unwrap();
+    assert_eq!(export.boards.len(), 2);
+    assert!(export.boards.iter().all(|x| x.tasks.is_empty()));
+}
+
+#[test]
+fn csv_writes_one_file_per_table() {
+    let store = sample_store();
+    let export = collect(&store, &TaskFilter::default()).unwrap();
+    let directory =
+        std::env::temp_dir().join(format!("task-manager-export-{}", std::process::id()));
+    let paths = write_csv(&export, &directory).unwrap();
+    assert_eq!(paths.len(), 5);
+
+    let tasks = std::fs::read_to_string(directory.join("tasks.csv")).unwrap();
+    let mut lines = tasks.lines();
+    assert_eq!(
+        lines.next().unwrap(),
+        "id,board_id,title,done,column,priority,created_at,completed_at,due_at,recurrence,parent_id,tags,blocked_by"
+    );
+    assert_eq!(tasks.lines().count(), 4);
+    assert!(tasks.contains("\"review, \"\"final\"\"\""));
+    let comments = std::fs::read_to_string(directory.join("comments.csv")).unwrap();
+    assert!(comments.contains("draft sent"));
+    std::fs::remove_dir_all(directory).unwrap();
+}
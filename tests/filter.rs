@@ -0,0 +1,149 @@
+use chrono::{DateTime, Duration, Utc};
+use task_manager::{
+    dao::SqliteStore,
+    store::{NewTask, TaskFilter, TaskSort, TaskStore},
+    Priority,
+};
+
+fn store_with_tasks(tasks: &[(&str, Option<DateTime<Utc>>)]) -> (SqliteStore, u16) {
+    let store = SqliteStore::open_in_memory().unwrap();
+    let board = store.add_board("work").unwrap();
+    for (title, due_at) in tasks {
+        store
+            .add_task(&NewTask {
+                due_at: *due_at,
+                ..NewTask::new(title, board)
+            })
+            .unwrap();
+    }
+    (store, board)
+}
+
+fn titles(store: &SqliteStore, filter: &TaskFilter) -> Vec<String> {
+    store
+        .list_tasks(filter)
+        .unwrap()
+        .into_iter()
+        .map(|x| x.title)
+        .collect()
+}
+
+#[test]
+fn text_match_is_bound_and_escaped() {
+    let (store, _) = store_with_tasks(&[("100% done", None), ("1000 done", None), ("it's", None)]);
+
+    let filter = TaskFilter {
+        text: Some("0%".to_string()),
+        ..Default::default()
+    };
+    assert_eq!(titles(&store, &filter), ["100% done"]);
+
+    let filter = TaskFilter {
+        text: Some("'s".to_string()),
+        ..Default::default()
+    };
+    assert_eq!(titles(&store, &filter), ["it's"]);
+}
+
+#[test]
+fn due_range_and_sort() {
+    let in_days = |days| Some(Utc::now() + Duration::days(days));
+    let (store, _) = store_with_tasks(&[
+        ("later", in_days(10)),
+        ("none", None),
+        ("soon", in_days(1)),
+        ("past", in_days(-1)),
+    ]);
+
+    let filter = TaskFilter {
+        due_after: Some(Utc::now()),
+        sort: TaskSort::Due,
+        ..Default::default()
+    };
+    assert_eq!(titles(&store, &filter), ["soon", "later"]);
+
+    let filter = TaskFilter {
+        sort: TaskSort::Due,
+        descending: true,
+        ..Default::default()
+    };
+    assert_eq!(titles(&store, &filter), ["later", "soon", "past", "none"]);
+}
+
+#[test]
+fn boards_limit_and_offset() {
+    let (store, work) = store_with_tasks(&[("a", None), ("b", None), ("c", None)]);
+    let home = store.add_board("home").unwrap();
+    store.add_task(&NewTask::new("d", home)).unwrap();
+
+    let filter = TaskFilter {
+        board_ids: vec![work, home],
+        sort: TaskSort::Title,
+        descending: true,
+        limit: Some(2),
+        offset: Some(1),
+        ..Default::default()
+    };
+    assert_eq!(titles(&store, &filter), ["c", "b"]);
+
+    let filter = TaskFilter::default().board(home);
+    assert_eq!(titles(&store, &filter), ["d"]);
+
+    let filter = TaskFilter {
+        offset: Some(3),
+        ..Default::default()
+    };
+    assert_eq!(titles(&store, &filter), ["d"]);
+}
+
+#[test]
+fn default_sort_is_most_urgent_first() {
+    let (store, board) = store_with_tasks(&[]);
+    for (title, priority) in [
+        ("whenever", Priority::Low),
+        ("fire", Priority::Urgent),
+        ("chore", Priority::Normal),
+        ("deadline", Priority::High),
+        ("another chore", Priority::Normal),
+    ] {
+        store
+            .add_task(&NewTask {
+                priority,
+                ..NewTask::new(title, board)
+            })
+            .unwrap();
+    }
+
+    assert_eq!(
+        titles(&store, &TaskFilter::default()),
+        ["fire", "deadline", "chore", "another chore", "whenever"]
+    );
+
+    let filter = TaskFilter {
+        descending: true,
+        ..Default::default()
+    };
+    assert_eq!(titles(&store, &filter)[0], "whenever");
+}
+
+#[test]
+fn tag_expressions() {
+    let (store, _) = store_with_tasks(&[("report", None), ("invoice", None), ("garden", None)]);
+    store
+        .set_task_tags(1, &["work".to_string(), "blocked".to_string()])
+        .unwrap();
+    store.set_task_tags(2, &["Work".to_string()]).unwrap();
+    store.set_task_tags(3, &["home".to_string()]).unwrap();
+
+    let filter = TaskFilter {
+        tags: "+work -blocked".parse().unwrap(),
+        ..Default::default()
+    };
+    assert_eq!(titles(&store, &filter), ["invoice"]);
+
+    let filter = TaskFilter {
+        tags: "-work".parse().unwrap(),
+        ..Default::default()
+    };
+    assert_eq!(titles(&store, &filter), ["garden"]);
+}
@@ -0,0 +1,142 @@
+use chrono::Duration;
+use task_manager::{
+    dao::SqliteStore,
+    dates::ReminderTime,
+    export::{collect, write_json},
+    import::{import, read_csv, read_json, read_todotxt, ImportOutcome, ImportTask},
+    store::{NewTask, TaskFilter, TaskStore},
+    Priority, TABLE_BOARDS, TABLE_TASKS,
+};
+
+fn store() -> SqliteStore {
+    SqliteStore::open_in_memory().unwrap()
+}
+
+#[test]
+fn json_export_round_trips() {
+    let source = store();
+    let board = source.add_board("work").unwrap();
+    let parent = source
+        .add_task(&NewTask {
+            priority: Priority::Urgent,
+            ..NewTask::new("release", board)
+        })
+        .unwrap();
+    let child = source
+        .add_task(&NewTask {
+            parent_id: Some(parent),
+            ..NewTask::new("changelog", board)
+        })
+        .unwrap();
+    let blocker = source.add_task(&NewTask::new("freeze", board)).unwrap();
+    source.add_dependency(child, blocker).unwrap();
+    source.set_task_tags(parent, &["q3".to_string()]).unwrap();
+    source.add_comment(parent, "tag it").unwrap();
+    let mut done = source.get_task(blocker).unwrap();
+    done.done = 1;
+    source.update_task(&done).unwrap();
+
+    let mut json = Vec::new();
+    write_json(
+        &collect(&source, &TaskFilter::default()).unwrap(),
+        &mut json,
+    )
+    .unwrap();
+    let tasks = read_json(json.as_slice()).unwrap();
+
+    let target = store();
+    target.add_board("unrelated").unwrap();
+    let report = import(&target, &tasks, "imported", false).unwrap();
+    assert_eq!(report.boards, ["work"]);
+    assert_eq!(report.count(ImportOutcome::Created), 3);
+
+    let id = |title: &str| report.tasks.iter().find(|x| x.title == title).unwrap().id;
+    let release = target.get_task(id("release")).unwrap();
+    assert_eq!(release.priority, Priority::Urgent);
+    assert_eq!(release.tags, ["q3"]);
+    assert_eq!(target.list_comments(release.id).unwrap()[0].title, "tag it");
+    let changelog = target.get_task(id("changelog")).unwrap();
+    assert_eq!(changelog.parent_id, Some(release.id));
+    assert_eq!(
+        target.list_blockers(changelog.id).unwrap()[0].id,
+        id("freeze")
+    );
+    let freeze = target.get_task(id("freeze")).unwrap();
+    assert_eq!((freeze.done, freeze.column.as_str()), (1, "Done"));
+    assert_eq!(
+        freeze.created_at,
+        source.get_task(blocker).unwrap().created_at
+    );
+
+    //Importing again finds every task
+    let report = import(&target, &tasks, "imported", false).unwrap();
+    assert_eq!(report.count(ImportOutcome::Duplicate), 3);
+    assert_eq!(target.count_records(TABLE_TASKS).unwrap(), 3);
+}
+
+#[test]
+fn csv_columns_are_mapped() {
+    let text = "Summary,Deadline,Project,Labels,Status\n\
+        Call Anna,2024-05-01T09:00:00Z,home,phone,\n\
+        Pay rent,,,\"bills, home\",x\n";
+    let mapping = "title=Summary,due=Deadline,board=Project,tags=Labels,done=Status"
+        .parse()
+        .unwrap();
+    let tasks = read_csv(text.as_bytes(), &mapping).unwrap();
+    assert_eq!(tasks.len(), 2);
+    assert_eq!(tasks[0].board.as_deref(), Some("home"));
+    assert!(tasks[0].due_at.is_some());
+    assert_eq!(tasks[1].tags, ["bills", "home"]);
+    assert!(tasks[1].done);
+
+    let store = store();
+    let report = import(&store, &tasks, "inbox", false).unwrap();
+    assert_eq!(report.boards, ["home", "inbox"]);
+    assert_eq!(store.get_task(report.tasks[1].id).unwrap().done, 1);
+
+    let error = read_csv(text.as_bytes(), &Default::default()).unwrap_err();
+    assert!(error.to_string().contains("title"));
+}
+
+#[test]
+fn duplicates_within_the_file_are_skipped() {
+    let tasks = read_todotxt("(B) call Anna +Home\ncall Anna +home @phone\n".as_bytes()).unwrap();
+    let store = store();
+    let report = import(&store, &tasks, "imported", false).unwrap();
+    assert_eq!(report.boards, ["Home"]);
+    assert_eq!(report.count(ImportOutcome::Created), 1);
+    assert_eq!(report.count(ImportOutcome::Duplicate), 1);
+    assert_eq!(
+        store.get_task(report.tasks[0].id).unwrap().priority,
+        Priority::High
+    );
+}
+
+#[test]
+fn dry_run_and_errors_save_nothing() {
+    let store = store();
+    let tasks = vec![ImportTask {
+        board: Some("work".to_string()),
+        title: "report".to_string(),
+        ..Default::default()
+    }];
+    let report = import(&store, &tasks, "imported", true).unwrap();
+    assert_eq!(report.count(ImportOutcome::Created), 1);
+    assert_eq!(store.count_records(TABLE_BOARDS).unwrap(), 0);
+    assert_eq!(store.count_records(TABLE_TASKS).unwrap(), 0);
+
+    //A reminder before the due date of a task without one fails the whole import
+    let mut tasks = tasks;
+    tasks.push(ImportTask {
+        title: "review".to_string(),
+        reminders: vec![ReminderTime::BeforeDue(Duration::hours(1))],
+        ..Default::default()
+    });
+    assert!(import(&store, &tasks, "imported", false).is_err());
+    assert_eq!(store.count_records(TABLE_BOARDS).unwrap(), 0);
+    assert_eq!(store.count_records(TABLE_TASKS).unwrap(), 0);
+
+    //The store is usable afterwards
+    store.add_board("work").unwrap();
+    assert_eq!(store.count_records(TABLE_BOARDS).unwrap(), 1);
+}
@@ -0,0 +1,204 @@
+use chrono::Duration;
+use task_manager::{
+    dao::SqliteStore,
+    dates::ReminderTime,
+    error::Result,
+    store::{NewTask, TaskFilter, TaskStore},
+    TABLE_BOARDS, TABLE_COLUMNS, TABLE_COMMENTS, TABLE_DEPENDENCIES, TABLE_REMINDERS, TABLE_TASKS,
+    TABLE_TASK_TAGS,
+};
+
+fn store() -> SqliteStore {
+    SqliteStore::open_in_memory().unwrap()
+}
+
+fn journaled(store: &SqliteStore, description: &str, mut f: impl FnMut() -> Result<()>) {
+    store.journaled(description, &mut f).unwrap();
+}
+
+fn counts(store: &SqliteStore) -> Vec<u16> {
+    [
+        TABLE_BOARDS,
+        TABLE_COLUMNS,
+        TABLE_TASKS,
+        TABLE_COMMENTS,
+        TABLE_REMINDERS,
+        TABLE_DEPENDENCIES,
+        TABLE_TASK_TAGS,
+    ]
+    .iter()
+    .map(|x| store.count_records(x).unwrap())
+    .collect()
+}
+
+#[test]
+fn deleted_board_is_restored_and_deleted_again() {
+    let store = store();
+    let mut board = 0;
+    journaled(&store, "setup", || {
+        board = store.add_board("work")?;
+        let parent = store.add_task(&NewTask {
+            due_at: Some(chrono::Utc::now() + Duration::days(1)),
+            ..NewTask::new("release", board)
+        })?;
+        let child = store.add_task(&NewTask {
+            parent_id: Some(parent),
+            ..NewTask::new("changelog", board)
+        })?;
+        store.add_dependency(parent, child)?;
+        store.add_comment(child, "draft in the wiki")?;
+        store.set_task_tags(parent, &["q3".to_string()])?;
+        store.add_reminder(parent, ReminderTime::BeforeDue(Duration::hours(1)))?;
+        Ok(())
+    });
+    let before = counts(&store);
+    let release = store.get_task(1).unwrap();
+
+    journaled(&store, "delete work", || store.delete_board(board));
+    assert_eq!(store.count_records(TABLE_TASKS).unwrap(), 0);
+
+    let undone = store.undo().unwrap().unwrap();
+    assert_eq!(
+        (undone.description.as_str(), undone.undone),
+        ("delete work", true)
+    );
+    assert_eq!(counts(&store), before);
+    let restored = store.get_task(1).unwrap();
+    assert_eq!(restored.tags, ["q3"]);
+    assert_eq!(restored.due_at, release.due_at);
+    assert_eq!(restored.column, release.column);
+    assert_eq!(store.get_task(2).unwrap().parent_id, Some(1));
+    assert_eq!(
+        store.list_comments(2).unwrap()[0].title,
+        "draft in the wiki"
+    );
+    //The search index follows
+    assert_eq!(store.search("changelog", 10).unwrap().len(), 1);
+
+    let redone = store.redo().unwrap().unwrap();
+    assert!(!redone.undone);
+    assert_eq!(store.count_records(TABLE_BOARDS).unwrap(), 0);
+    assert_eq!(store.count_records(TABLE_COMMENTS).unwrap(), 0);
+    assert!(store.redo().unwrap().is_none());
+
+    //Undo down to the empty store
+    store.undo().unwrap();
+    store.undo().unwrap();
+    assert!(counts(&store).iter().all(|x| *x == 0));
+    assert!(store.undo().unwrap().is_none());
+}
+
+#[test]
+fn edits_and_status_changes_are_undone() {
+    let store = store();
+    let board = store.add_board("work").unwrap();
+    let id = store.add_task(&NewTask::new("report", board)).unwrap();
+
+    journaled(&store, "edit", || {
+        let mut task = store.get_task(id)?;
+        task.title = "quarterly report".to_string();
+        store.update_task(&task)
+    });
+    journaled(&store, "done", || {
+        let mut task = store.get_task(id)?;
+        task.done = 1;
+        store.update_task(&task)
+    });
+    let done = store.get_task(id).unwrap();
+    assert_eq!((done.done, done.column.as_str()), (1, "Done"));
+    assert!(done.completed_at.is_some());
+
+    store.undo().unwrap();
+    let task = store.get_task(id).unwrap();
+    assert_eq!((task.done, task.column.as_str()), (0, "Todo"));
+    assert!(task.completed_at.is_none());
+    assert_eq!(task.title, "quarterly report");
+
+    store.undo().unwrap();
+    assert_eq!(store.get_task(id).unwrap().title, "report");
+    assert_eq!(store.search("quarterly", 10).unwrap().len(), 0);
+
+    store.redo().unwrap();
+    store.redo().unwrap();
+    assert_eq!(store.get_task(id).unwrap().done, 1);
+}
+
+#[test]
+fn new_changes_clear_the_redo_stack() {
+    let store = store();
+    let board = store.add_board("work").unwrap();
+    journaled(&store, "first", || {
+        store.add_task(&NewTask::new("a", board)).map(|_| ())
+    });
+    journaled(&store, "second", || {
+        store.add_task(&NewTask::new("b", board)).map(|_| ())
+    });
+    store.undo().unwrap();
+    assert!(store.list_operations(10).unwrap()[0].undone);
+
+    journaled(&store, "third", || {
+        store.add_task(&NewTask::new("c", board)).map(|_| ())
+    });
+    assert!(store.redo().unwrap().is_none());
+    let operations = store.list_operations(10).unwrap();
+    let descriptions: Vec<&str> = operations.iter().map(|x| x.description.as_str()).collect();
+    assert_eq!(descriptions, ["third", "first"]);
+}
+
+#[test]
+fn operations_without_changes_are_not_kept() {
+    let store = store();
+    let board = store.add_board("work").unwrap();
+    journaled(&store, "list", || {
+        store.list_tasks(&TaskFilter::pending().board(board))?;
+        Ok(())
+    });
+    //Nested operations join the outer one
+    journaled(&store, "outer", || {
+        store.add_task(&NewTask::new("a", board))?;
+        store.journaled("inner", &mut || {
+            store.add_task(&NewTask::new("b", board)).map(|_| ())
+        })
+    });
+    let operations = store.list_operations(10).unwrap();
+    assert_eq!(operations.len(), 1);
+    assert_eq!(operations[0].description, "outer");
+
+    store.undo().unwrap();
+    assert_eq!(store.count_records(TABLE_TASKS).unwrap(), 0);
+}
+
+#[test]
+fn operation_ids_grow_past_u16() {
+    let store = store();
+    store
+        .connection()
+        .execute(
+            "INSERT INTO operations (id, description, created_at) VALUES (70000, 'old', '2026-10-18T09:00:00Z')",
+            [],
+        )
+        .unwrap();
+    journaled(&store, "board", || store.add_board("work").map(|_| ()));
+
+    let undone = store.undo().unwrap().unwrap();
+    assert_eq!((undone.id, undone.description.as_str()), (70001, "board"));
+    assert_eq!(store.count_records(TABLE_BOARDS).unwrap(), 0);
+}
+
+#[test]
+fn failed_operations_are_rolled_back() {
+    let store = store();
+    store.add_board("home").unwrap();
+    let result = store.journaled("half done", &mut || {
+        store.add_board("work")?;
+        store.get_board(99)?;
+        store.add_board("later").map(|_| ())
+    });
+    assert!(result.is_err());
+    assert_eq!(store.count_records(TABLE_BOARDS).unwrap(), 1);
+    assert!(store.list_operations(10).unwrap().is_empty());
+
+    //Recording stops with the failed operation
+    store.add_board("work").unwrap();
+    assert!(store.list_operations(10).unwrap().is_empty());
+}
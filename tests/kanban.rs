@@ -0,0 +1,107 @@
+use ratatui::{backend::TestBackend, crossterm::event::KeyCode, Terminal};
+use task_manager::{
+    dao::SqliteStore,
+    kanban::Kanban,
+    store::{NewTask, TaskStore},
+};
+
+fn board_with_tasks(titles: &[&str]) -> (SqliteStore, u16) {
+    let store = SqliteStore::open_in_memory().unwrap();
+    let board = store.add_board("work").unwrap();
+    for title in titles {
+        store.add_task(&NewTask::new(title, board)).unwrap();
+    }
+    (store, board)
+}
+
+fn press(kanban: &mut Kanban, store: &SqliteStore, keys: &[KeyCode]) {
+    for key in keys {
+        assert!(kanban.handle_key(store, *key).unwrap());
+    }
+}
+
+#[test]
+fn tasks_are_grouped_by_column() {
+    let (store, board) = board_with_tasks(&["report", "invoice"]);
+    let mut kanban = Kanban::load(&store, board).unwrap();
+    assert_eq!(
+        kanban.tasks.iter().map(Vec::len).collect::<Vec<_>>(),
+        [2, 0, 0]
+    );
+
+    press(
+        &mut kanban,
+        &store,
+        &[KeyCode::Down, KeyCode::Down, KeyCode::Right],
+    );
+    assert_eq!((kanban.column, kanban.row), (1, 0));
+    assert!(kanban.selected().is_none());
+    press(&mut kanban, &store, &[KeyCode::Left, KeyCode::Down]);
+    assert_eq!(kanban.selected().unwrap().title, "invoice");
+    press(&mut kanban, &store, &[KeyCode::Down]);
+    assert_eq!(kanban.row, 1);
+}
+
+#[test]
+fn selected_task_is_moved_and_completed() {
+    let (store, board) = board_with_tasks(&["report", "invoice"]);
+    let mut kanban = Kanban::load(&store, board).unwrap();
+
+    press(&mut kanban, &store, &[KeyCode::Char('>')]);
+    assert_eq!(kanban.column, 1);
+    let id = kanban.selected().unwrap().id;
+    assert_eq!(store.get_task(id).unwrap().column, "Doing");
+
+    press(&mut kanban, &store, &[KeyCode::Char('d')]);
+    assert_eq!(kanban.column, 2);
+    assert_eq!(store.get_task(id).unwrap().done, 1);
+
+    press(&mut kanban, &store, &[KeyCode::Char('d')]);
+    assert_eq!(kanban.column, 0);
+    assert_eq!(store.get_task(id).unwrap().done, 0);
+    assert!(!kanban.handle_key(&store, KeyCode::Char('q')).unwrap());
+}
+
+#[test]
+fn board_and_comments_are_rendered() {
+    let (store, board) = board_with_tasks(&["report"]);
+    store.add_comment(1, "first draft").unwrap();
+    let mut kanban = Kanban::load(&store, board).unwrap();
+    let mut terminal = Terminal::new(TestBackend::new(90, 20)).unwrap();
+
+    let screen = |terminal: &mut Terminal<TestBackend>, kanban: &Kanban| {
+        terminal.draw(|frame| kanban.render(frame)).unwrap();
+        let buffer = terminal.backend().buffer();
+        buffer
+            .content()
+            .iter()
+            .map(|x| x.symbol())
+            .collect::<String>()
+    };
+    let text = screen(&mut terminal, &kanban);
+    for expected in ["Todo (1)", "Doing (0)", "Done (0)", "1 report"] {
+        assert!(text.contains(expected), "{} missing", expected);
+    }
+
+    press(&mut kanban, &store, &[KeyCode::Char('c')]);
+    assert!(screen(&mut terminal, &kanban).contains("first draft"));
+    press(&mut kanban, &store, &[KeyCode::Esc]);
+    assert!(kanban.comments.is_none());
+}
+
+#[test]
+fn each_move_is_undone_on_its_own() {
+    let (store, board) = board_with_tasks(&["report"]);
+    let mut kanban = Kanban::load(&store, board).unwrap();
+    press(
+        &mut kanban,
+        &store,
+        &[KeyCode::Char('>'), KeyCode::Char('d'), KeyCode::Char('c')],
+    );
+    let operations = store.list_operations(10).unwrap();
+    let descriptions: Vec<&str> = operations.iter().map(|x| x.description.as_str()).collect();
+    assert_eq!(descriptions, ["Complete task 1", "Move task 1 to Doing"]);
+
+    store.undo().unwrap();
+    assert_eq!(store.get_task(1).unwrap().column, "Doing");
+}
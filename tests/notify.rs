@@ -0,0 +1,104 @@
+use chrono::{Duration, Utc};
+use task_manager::{
+    dao::SqliteStore,
+    dates::ReminderTime,
+    notify::{fire_due_reminders, Notifier},
+    store::{NewTask, TaskStore},
+};
+
+fn store_with_reminders(reminders: &[(&str, i64)]) -> SqliteStore {
+    let store = SqliteStore::open_in_memory().unwrap();
+    let board = store.add_board("work").unwrap();
+    for (title, minutes) in reminders {
+        let id = store.add_task(&NewTask::new(title, board)).unwrap();
+        let remind_at = Utc::now() + Duration::minutes(*minutes);
+        store.add_reminder(id, ReminderTime::At(remind_at)).unwrap();
+    }
+    store
+}
+
+#[test]
+fn due_reminders_fire_once() {
+    let store = store_with_reminders(&[("late", -5), ("later", 30)]);
+
+    let fired = fire_due_reminders(&store, &[], Utc::now()).unwrap();
+    assert_eq!(fired.len(), 1);
+    assert_eq!(fired[0].title, "late");
+    assert!(store.list_reminders(fired[0].id).unwrap()[0]
+        .fired_at
+        .is_some());
+
+    assert!(fire_due_reminders(&store, &[], Utc::now())
+        .unwrap()
+        .is_empty());
+
+    let in_an_hour = Utc::now() + Duration::hours(1);
+    let fired = fire_due_reminders(&store, &[], in_an_hour).unwrap();
+    assert_eq!(fired.len(), 1);
+    assert_eq!(fired[0].title, "later");
+}
+
+#[test]
+fn snoozed_reminder_fires_again() {
+    let store = store_with_reminders(&[("late", -5)]);
+    let id = fire_due_reminders(&store, &[], Utc::now()).unwrap()[0].id;
+
+    let snooze = Utc::now() + Duration::minutes(10);
+    store.add_reminder(id, ReminderTime::At(snooze)).unwrap();
+
+    assert!(store.list_due_reminders(Utc::now()).unwrap().is_empty());
+    let in_an_hour = Utc::now() + Duration::hours(1);
+    assert_eq!(store.list_due_reminders(in_an_hour).unwrap().len(), 1);
+}
+
+#[test]
+fn every_reminder_of_a_task_fires() {
+    let store = SqliteStore::open_in_memory().unwrap();
+    let board = store.add_board("work").unwrap();
+    let id = store
+        .add_task(&NewTask {
+            due_at: Some(Utc::now() + Duration::hours(3)),
+            ..NewTask::new("call", board)
+        })
+        .unwrap();
+    store
+        .add_reminder(id, ReminderTime::BeforeDue(Duration::hours(1)))
+        .unwrap();
+    store
+        .add_reminder(id, ReminderTime::BeforeDue(Duration::zero()))
+        .unwrap();
+
+    let in_two_hours = Utc::now() + Duration::hours(2) + Duration::minutes(1);
+    assert_eq!(
+        fire_due_reminders(&store, &[], in_two_hours).unwrap().len(),
+        1
+    );
+    let in_four_hours = Utc::now() + Duration::hours(4);
+    assert_eq!(
+        fire_due_reminders(&store, &[], in_four_hours)
+            .unwrap()
+            .len(),
+        1
+    );
+}
+
+#[test]
+fn reminders_of_done_tasks_do_not_fire() {
+    let store = store_with_reminders(&[("late", -5)]);
+    let mut task = store.get_task(1).unwrap();
+    task.done = 1;
+    store.update_task(&task).unwrap();
+
+    assert!(fire_due_reminders(&store, &[], Utc::now())
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn failing_notifier_does_not_stop_firing() {
+    let store = store_with_reminders(&[("late", -5)]);
+    let notifiers = [Notifier::Command("exit 1".to_string())];
+
+    let fired = fire_due_reminders(&store, &notifiers, Utc::now()).unwrap();
+    assert_eq!(fired.len(), 1);
+}
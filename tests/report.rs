@@ -0,0 +1,96 @@
+use chrono::{Duration, Utc};
+use task_manager::{
+    dao::SqliteStore,
+    report::{build, to_html, to_markdown},
+    store::{NewTask, TaskStore},
+    Priority,
+};
+
+fn sample_store() -> SqliteStore {
+    let store = SqliteStore::open_in_memory().unwrap();
+    let work = store.add_board("work").unwrap();
+    store.add_board("home").unwrap();
+    let late = store
+        .add_task(&NewTask {
+            priority: Priority::Urgent,
+            due_at: Some(Utc::now() - Duration::days(1)),
+            ..NewTask::new("send <invoice>", work)
+        })
+        .unwrap();
+    store.add_comment(late, "client asked twice").unwrap();
+    let shipped = store.add_task(&NewTask::new("ship v2", work)).unwrap();
+    let mut task = store.get_task(shipped).unwrap();
+    task.done = 1;
+    store.update_task(&task).unwrap();
+    store
+}
+
+#[test]
+fn markdown_groups_by_board_and_status() {
+    let store = sample_store();
+    let report = build(&store, &[], None, None).unwrap();
+    assert_eq!(report.boards.len(), 2);
+    assert_eq!(report.boards[0].pending.len(), 1);
+    assert_eq!(report.boards[0].done.len(), 1);
+
+    let text = to_markdown(&report);
+    assert!(text.contains(
+        "## work\n\n### Pending (1)\n\n- **send \\<invoice\\> (overdue)** #1, urgent, due "
+    ));
+    assert!(text.contains("  - client asked twice _"));
+    assert!(text.contains("### Done (1)\n\n- ship v2 #2\n"));
+    assert!(text.contains("## home\n\n### Pending (0)\n\n_No tasks_"));
+}
+
+#[test]
+fn markdown_escapes_titles_comments_and_tags() {
+    let store = SqliteStore::open_in_memory().unwrap();
+    let board = store.add_board("q3 #1").unwrap();
+    let id = store.add_task(&NewTask::new("a|b *c*", board)).unwrap();
+    store.set_task_tags(id, &["to_do".to_string()]).unwrap();
+    store.add_comment(id, "see [notes]").unwrap();
+
+    let text = to_markdown(&build(&store, &[], None, None).unwrap());
+    assert!(text.contains("## q3 \\#1"));
+    assert!(text.contains("- a\\|b \\*c\\* #1, to\\_do\n"));
+    assert!(text.contains("  - see \\[notes\\] _"));
+}
+
+#[test]
+fn html_escapes_titles() {
+    let store = sample_store();
+    let html = to_html(&build(&store, &[1], None, None).unwrap());
+    assert!(html.contains("<span class=\"overdue\">send &lt;invoice&gt; (overdue)</span>"));
+    assert!(html.contains("<li>client asked twice"));
+    assert!(!html.contains("<h2>home</h2>"));
+}
+
+#[test]
+fn date_range_applies_to_creation_and_completion() {
+    let store = sample_store();
+    let tomorrow = Utc::now() + Duration::days(1);
+    let report = build(&store, &[], Some(tomorrow), None).unwrap();
+    assert!(report
+        .boards
+        .iter()
+        .all(|x| x.pending.is_empty() && x.done.is_empty()));
+    let report = build(&store, &[], None, Some(tomorrow)).unwrap();
+    assert_eq!(report.boards[0].pending.len(), 1);
+    assert_eq!(report.boards[0].done.len(), 1);
+
+    //Created long ago, completed today
+    let yesterday = Utc::now() - Duration::days(1);
+    let mut task = store.get_task(1).unwrap();
+    task.done = 1;
+    store.update_task(&task).unwrap();
+    store
+        .connection()
+        .execute(
+            "UPDATE tasks SET created_at = ?1",
+            [yesterday - Duration::days(1)],
+        )
+        .unwrap();
+    let report = build(&store, &[], Some(yesterday), None).unwrap();
+    assert!(report.boards[0].pending.is_empty());
+    assert_eq!(report.boards[0].done.len(), 2);
+}
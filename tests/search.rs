@@ -0,0 +1,69 @@
+use task_manager::{
+    dao::SqliteStore,
+    search::{to_fts_query, HIGHLIGHT_END, HIGHLIGHT_START},
+    store::{NewTask, TaskStore},
+};
+
+fn store_with_tasks(titles: &[&str]) -> SqliteStore {
+    let store = SqliteStore::open_in_memory().unwrap();
+    let board = store.add_board("work").unwrap();
+    for title in titles {
+        store.add_task(&NewTask::new(title, board)).unwrap();
+    }
+    store
+}
+
+fn result_ids(store: &SqliteStore, text: &str) -> Vec<u16> {
+    store
+        .search(text, 10)
+        .unwrap()
+        .into_iter()
+        .map(|x| x.task.id)
+        .collect()
+}
+
+#[test]
+fn user_text_is_quoted_as_prefix_terms() {
+    assert_eq!(
+        to_fts_query(r#"rep "or" NOT"#).unwrap(),
+        r#""rep"* """or"""* "NOT"*"#
+    );
+    assert!(to_fts_query("   ").is_none());
+}
+
+#[test]
+fn titles_and_comments_are_searched_by_prefix() {
+    let store = store_with_tasks(&["Write quarterly report", "Buy milk", "Call Anna"]);
+    store.add_comment(2, "for the report meeting").unwrap();
+
+    assert_eq!(result_ids(&store, "repo"), [1, 2]);
+    assert_eq!(result_ids(&store, "report meeting"), [2]);
+    assert!(result_ids(&store, "holiday").is_empty());
+}
+
+#[test]
+fn index_follows_updates_and_deletes() {
+    let store = store_with_tasks(&["Write report", "Buy milk"]);
+    store.add_comment(2, "semi-skimmed").unwrap();
+
+    let mut task = store.get_task(1).unwrap();
+    task.title = "Write summary".to_string();
+    store.update_task(&task).unwrap();
+    assert!(result_ids(&store, "report").is_empty());
+    assert_eq!(result_ids(&store, "summary"), [1]);
+
+    store.delete_task(2).unwrap();
+    assert!(result_ids(&store, "skimmed").is_empty());
+}
+
+#[test]
+fn matches_are_highlighted() {
+    let store = store_with_tasks(&["Write report"]);
+    store.add_comment(1, "draft of the report is done").unwrap();
+
+    let result = &store.search("report", 10).unwrap()[0];
+    let highlighted = format!("{}report{}", HIGHLIGHT_START, HIGHLIGHT_END);
+    assert_eq!(result.title, format!("Write {}", highlighted));
+    assert_eq!(result.comments.len(), 1);
+    assert!(result.comments[0].contains(&highlighted));
+}
@@ -0,0 +1,882 @@
+use chrono::{Duration, Local, SubsecRound, TimeZone, Utc};
+use task_manager::{
+    complete_subtasks, complete_with_subtasks,
+    dao::SqliteStore,
+    dates::ReminderTime,
+    error::Error,
+    get_overdue_tasks, get_ready_tasks,
+    history::HistoryAction,
+    migrations, set_task_column, set_task_done,
+    store::{NewTask, TaskFilter, TaskStore},
+    Priority, Progress, TABLE_BOARDS, TABLE_TASKS,
+};
+
+fn store() -> SqliteStore {
+    SqliteStore::open_in_memory().unwrap()
+}
+
+fn new_task(title: &str, board_id: u16) -> NewTask {
+    NewTask::new(title, board_id)
+}
+
+#[test]
+fn new_store_is_migrated() {
+    let store = store();
+    let version = migrations::get_schema_version(store.connection()).unwrap();
+    assert_eq!(version, migrations::latest_version());
+}
+
+#[test]
+fn newer_schema_is_refused() {
+    let conn = rusqlite::Connection::open_in_memory().unwrap();
+    conn.pragma_update(None, "user_version", migrations::latest_version() + 1)
+        .unwrap();
+
+    let result = SqliteStore::new(conn);
+    assert!(matches!(result, Err(Error::SchemaTooNew { .. })));
+}
+
+#[test]
+fn legacy_timestamps_are_migrated() {
+    let conn = rusqlite::Connection::open_in_memory().unwrap();
+    for migration in &migrations::MIGRATIONS[..4] {
+        (migration.up)(&conn).unwrap();
+    }
+    conn.execute_batch(
+        "PRAGMA user_version = 4;
+        INSERT INTO boards (title) VALUES ('work');
+        INSERT INTO tasks (title, done, board_id, created_at, reminder)
+            VALUES ('report', 0, 1, 'Sun, Oct 18 2026 08:56:54', 'Mon, Nov  2 2026 14:30:00');
+        INSERT INTO tasks (title, done, board_id, created_at, reminder)
+            VALUES ('meeting', 0, 1, 'Sun, Oct 18 2026 09:00:00', '');
+        INSERT INTO comments (title, task_id, created_at)
+            VALUES ('first draft', 1, 'Sun, 18 Oct 2026 10:00:00 +0200');",
+    )
+    .unwrap();
+
+    let store = SqliteStore::new(conn).unwrap();
+
+    let task = store.get_task(1).unwrap();
+    assert_eq!(task.created_at, Utc.ymd(2026, 10, 18).and_hms(8, 56, 54));
+    let due_at = Utc.ymd(2026, 11, 2).and_hms(14, 30, 0);
+    assert_eq!(task.due_at, Some(due_at));
+    assert_eq!(task.column, "Todo");
+    let reminders = store.list_reminders(1).unwrap();
+    assert_eq!(reminders.len(), 1);
+    assert_eq!(reminders[0].remind_at, due_at);
+    assert_eq!(reminders[0].before_due, Some(Duration::zero()));
+    assert_eq!(store.get_task(2).unwrap().due_at, None);
+    assert!(store.list_reminders(2).unwrap().is_empty());
+    let comments = store.list_comments(1).unwrap();
+    assert_eq!(
+        comments[0].created_at,
+        Utc.ymd(2026, 10, 18).and_hms(8, 0, 0)
+    );
+    assert_eq!(store.search("report", 10).unwrap().len(), 1);
+}
+
+#[test]
+fn unreadable_legacy_timestamp_fails_the_migration() {
+    let conn = rusqlite::Connection::open_in_memory().unwrap();
+    for migration in &migrations::MIGRATIONS[..4] {
+        (migration.up)(&conn).unwrap();
+    }
+    conn.execute_batch(
+        "PRAGMA user_version = 4;
+        INSERT INTO boards (title) VALUES ('work');
+        INSERT INTO tasks (title, done, board_id, created_at, reminder)
+            VALUES ('report', 0, 1, 'yesterday', '');",
+    )
+    .unwrap();
+
+    let result = SqliteStore::new(conn);
+    assert!(matches!(result, Err(Error::Migration { .. })));
+}
+
+#[test]
+fn new_timestamps_are_whole_seconds() {
+    let store = store();
+    let board = store.add_board("work").unwrap();
+    let id = store.add_task(&new_task("report", board)).unwrap();
+
+    let created_at = store.get_task(id).unwrap().created_at;
+    assert_eq!(created_at, created_at.trunc_subsecs(0));
+}
+
+#[test]
+fn boards_can_be_added_renamed_and_deleted() {
+    let store = store();
+    let id = store.add_board("work").unwrap();
+    store.add_board("home").unwrap();
+    assert_eq!(store.count_records(TABLE_BOARDS).unwrap(), 2);
+
+    store.rename_board(id, "office").unwrap();
+    assert_eq!(store.get_board(id).unwrap().title, "office");
+
+    store.delete_board(id).unwrap();
+    assert!(matches!(store.get_board(id), Err(Error::NotFound(_))));
+    assert_eq!(store.list_boards().unwrap().len(), 1);
+}
+
+#[test]
+fn tasks_are_filtered_by_status_and_board() {
+    let store = store();
+    let work = store.add_board("work").unwrap();
+    let home = store.add_board("home").unwrap();
+    let report = store.add_task(&new_task("report", work)).unwrap();
+    store.add_task(&new_task("meeting", work)).unwrap();
+    store.add_task(&new_task("groceries", home)).unwrap();
+
+    let mut task = store.get_task(report).unwrap();
+    task.done = 1;
+    store.update_task(&task).unwrap();
+
+    let all = store.list_tasks(&TaskFilter::default()).unwrap();
+    assert_eq!(all.len(), 3);
+
+    let pending = store.list_tasks(&TaskFilter::pending()).unwrap();
+    assert_eq!(pending.len(), 2);
+
+    let done_at_work = TaskFilter::done(true).board(work);
+    let tasks = store.list_tasks(&done_at_work).unwrap();
+    assert_eq!(tasks.len(), 1);
+    assert_eq!(tasks[0].title, "report");
+}
+
+#[test]
+fn task_update_saves_title_board_and_due_date() {
+    let store = store();
+    let work = store.add_board("work").unwrap();
+    let home = store.add_board("home").unwrap();
+    let id = store.add_task(&new_task("report", work)).unwrap();
+
+    let mut task = store.get_task(id).unwrap();
+    task.title = "final report".to_string();
+    task.board_id = home;
+    let due_at = Utc.ymd(2026, 11, 2).and_hms(14, 30, 0);
+    task.due_at = Some(due_at);
+    store.update_task(&task).unwrap();
+
+    let task = store.get_task(id).unwrap();
+    assert_eq!(task.title, "final report");
+    assert_eq!(task.board_id, home);
+    assert_eq!(task.due_at, Some(due_at));
+    assert_eq!(task.column, "Todo");
+}
+
+#[test]
+fn relative_reminders_follow_the_due_date() {
+    let store = store();
+    let board = store.add_board("work").unwrap();
+    let due_at = Utc.ymd(2026, 11, 2).and_hms(14, 30, 0);
+    let id = store
+        .add_task(&NewTask {
+            due_at: Some(due_at),
+            ..new_task("report", board)
+        })
+        .unwrap();
+    let fixed = Utc.ymd(2026, 11, 1).and_hms(9, 0, 0);
+    store.add_reminder(id, ReminderTime::At(fixed)).unwrap();
+    store
+        .add_reminder(id, ReminderTime::BeforeDue(Duration::hours(1)))
+        .unwrap();
+
+    let mut task = store.get_task(id).unwrap();
+    task.due_at = Some(due_at + Duration::days(1));
+    store.update_task(&task).unwrap();
+    let remind_at: Vec<_> = store
+        .list_reminders(id)
+        .unwrap()
+        .into_iter()
+        .map(|x| x.remind_at)
+        .collect();
+    assert_eq!(remind_at, [fixed, due_at + Duration::hours(23)]);
+
+    task.due_at = None;
+    store.update_task(&task).unwrap();
+    let reminders = store.list_reminders(id).unwrap();
+    assert_eq!(reminders.len(), 1);
+    assert_eq!(reminders[0].remind_at, fixed);
+}
+
+#[test]
+fn relative_reminder_needs_a_due_date() {
+    let store = store();
+    let board = store.add_board("work").unwrap();
+    let id = store.add_task(&new_task("report", board)).unwrap();
+
+    let result = store.add_reminder(id, ReminderTime::BeforeDue(Duration::hours(1)));
+    assert!(matches!(result, Err(Error::Validation(_))));
+}
+
+#[test]
+fn missing_task_is_not_found() {
+    let store = store();
+    assert!(matches!(store.get_task(42), Err(Error::NotFound(_))));
+}
+
+#[test]
+fn task_with_unknown_board_is_rejected() {
+    let store = store();
+    assert!(store.add_task(&new_task("report", 7)).is_err());
+}
+
+#[test]
+fn comments_are_listed_per_task() {
+    let store = store();
+    let board = store.add_board("work").unwrap();
+    let report = store.add_task(&new_task("report", board)).unwrap();
+    let meeting = store.add_task(&new_task("meeting", board)).unwrap();
+
+    store.add_comment(report, "first draft").unwrap();
+    store.add_comment(report, "reviewed").unwrap();
+    store.add_comment(meeting, "room 4").unwrap();
+
+    let comments = store.list_comments(report).unwrap();
+    assert_eq!(comments.len(), 2);
+    assert_eq!(comments[0].title, "first draft");
+}
+
+#[test]
+fn deleting_a_board_cascades_to_tasks_and_comments() {
+    let store = store();
+    let board = store.add_board("work").unwrap();
+    let task = store.add_task(&new_task("report", board)).unwrap();
+    store.add_comment(task, "first draft").unwrap();
+
+    store.delete_board(board).unwrap();
+
+    assert_eq!(store.count_records(TABLE_TASKS).unwrap(), 0);
+    assert!(store.list_comments(task).unwrap().is_empty());
+}
+
+#[test]
+fn deleting_a_task_cascades_to_comments() {
+    let store = store();
+    let board = store.add_board("work").unwrap();
+    let task = store.add_task(&new_task("report", board)).unwrap();
+    store.add_comment(task, "first draft").unwrap();
+
+    store.delete_task(task).unwrap();
+
+    assert!(store.list_comments(task).unwrap().is_empty());
+}
+
+#[test]
+fn past_due_pending_tasks_are_overdue() {
+    let store = store();
+    let board = store.add_board("work").unwrap();
+    let past = Some(Utc::now() - Duration::days(1));
+    let future = Some(Utc::now() + Duration::days(1));
+
+    for (title, due_at) in [("late", past), ("upcoming", future), ("none", None)] {
+        store
+            .add_task(&NewTask {
+                due_at,
+                ..NewTask::new(title, board)
+            })
+            .unwrap();
+    }
+    let done = store
+        .add_task(&NewTask {
+            due_at: past,
+            ..NewTask::new("late but done", board)
+        })
+        .unwrap();
+    let mut task = store.get_task(done).unwrap();
+    task.done = 1;
+    store.update_task(&task).unwrap();
+
+    let overdue = get_overdue_tasks(&store).unwrap();
+    assert_eq!(overdue.len(), 1);
+    assert_eq!(overdue[0].title, "late");
+}
+
+#[test]
+fn orphans_are_reported_and_repaired() {
+    let store = store();
+    let board = store.add_board("work").unwrap();
+    store.add_task(&new_task("report", board)).unwrap();
+
+    store
+        .connection()
+        .execute_batch(
+            "PRAGMA foreign_keys = OFF;
+            INSERT INTO tasks (title, done, board_id, created_at) VALUES ('lost', 0, 99, '2026-10-18 08:00:00+00:00');
+            INSERT INTO comments (title, task_id, created_at) VALUES ('lost', 98, '2026-10-18 08:00:00+00:00');
+            PRAGMA foreign_keys = ON;",
+        )
+        .unwrap();
+
+    assert_eq!(store.check_integrity().unwrap().len(), 2);
+    assert_eq!(store.repair_orphans().unwrap(), 2);
+    assert!(store.check_integrity().unwrap().is_empty());
+    assert_eq!(store.count_records(TABLE_TASKS).unwrap(), 1);
+}
+
+#[test]
+fn pending_tasks_are_moved_between_boards() {
+    let store = store();
+    let work = store.add_board("work").unwrap();
+    let home = store.add_board("home").unwrap();
+    store.add_task(&new_task("report", work)).unwrap();
+    store.add_task(&new_task("meeting", work)).unwrap();
+    let done = store.add_task(&new_task("invoice", work)).unwrap();
+    let mut task = store.get_task(done).unwrap();
+    task.done = 1;
+    store.update_task(&task).unwrap();
+
+    let moved = store
+        .move_tasks(&TaskFilter::pending().board(work), home)
+        .unwrap();
+
+    assert_eq!(moved, 2);
+    assert_eq!(
+        store
+            .list_tasks(&TaskFilter::default().board(home))
+            .unwrap()
+            .len(),
+        2
+    );
+    let left = store
+        .list_tasks(&TaskFilter::default().board(work))
+        .unwrap();
+    assert_eq!(left.len(), 1);
+    assert_eq!(left[0].title, "invoice");
+}
+
+#[test]
+fn completing_a_recurring_task_creates_the_next_occurrence() {
+    let store = store();
+    let board = store.add_board("home").unwrap();
+    let due_at = (Utc::now() + Duration::hours(1)).trunc_subsecs(0);
+    let id = store
+        .add_task(&NewTask {
+            due_at: Some(due_at),
+            recurrence: Some("every 3 days".parse().unwrap()),
+            ..NewTask::new("water plants", board)
+        })
+        .unwrap();
+    store
+        .add_reminder(id, ReminderTime::BeforeDue(Duration::minutes(30)))
+        .unwrap();
+
+    let next = set_task_done(&store, id, true).unwrap().unwrap();
+
+    let done = store.get_task(id).unwrap();
+    assert_eq!(done.done, 1);
+    assert_eq!(done.recurrence, None);
+    let next = store.get_task(next).unwrap();
+    assert_eq!(next.title, "water plants");
+    assert_eq!(next.done, 0);
+    let next_due = due_at + Duration::days(3);
+    assert_eq!(next.due_at, Some(next_due));
+    let reminders = store.list_reminders(next.id).unwrap();
+    assert_eq!(reminders[0].remind_at, next_due - Duration::minutes(30));
+    assert_eq!(
+        next.recurrence.unwrap().to_string(),
+        "FREQ=DAILY;INTERVAL=3"
+    );
+
+    //Reopening and completing again doesn't spawn a second occurrence
+    set_task_done(&store, id, false).unwrap();
+    assert_eq!(set_task_done(&store, id, true).unwrap(), None);
+    assert_eq!(store.count_records(TABLE_TASKS).unwrap(), 2);
+}
+
+#[test]
+fn recurring_task_stays_pending_when_its_next_occurrence_fails() {
+    let store = store();
+    let board = store.add_board("home").unwrap();
+    let id = store
+        .add_task(&NewTask {
+            recurrence: Some("daily".parse().unwrap()),
+            ..NewTask::new("water plants", board)
+        })
+        .unwrap();
+    store
+        .connection()
+        .execute_batch(
+            "CREATE TEMP TRIGGER refuse_tasks BEFORE INSERT ON main.tasks
+            BEGIN SELECT RAISE(ABORT, 'disk full'); END;",
+        )
+        .unwrap();
+
+    assert!(set_task_done(&store, id, true).is_err());
+    let task = store.get_task(id).unwrap();
+    assert_eq!(task.done, 0);
+    assert!(task.recurrence.is_some());
+    assert!(task.completed_at.is_none());
+}
+
+#[test]
+fn subtasks_show_progress_and_block_completion() {
+    let store = store();
+    let board = store.add_board("work").unwrap();
+    let parent = store.add_task(&new_task("release", board)).unwrap();
+    let mut subtasks = Vec::new();
+    for title in ["tag", "build", "publish"] {
+        let id = store
+            .add_task(&NewTask {
+                parent_id: Some(parent),
+                ..new_task(title, board)
+            })
+            .unwrap();
+        subtasks.push(id);
+    }
+    set_task_done(&store, subtasks[0], true).unwrap();
+
+    let task = store.get_task(parent).unwrap();
+    assert_eq!(task.subtasks, Progress { done: 1, total: 3 });
+    assert_eq!(task.subtasks.to_string(), "1/3");
+    let result = set_task_done(&store, parent, true);
+    assert!(matches!(result, Err(Error::Validation(_))));
+
+    assert_eq!(complete_subtasks(&store, parent).unwrap(), 2);
+    set_task_done(&store, parent, true).unwrap();
+    assert_eq!(store.get_task(parent).unwrap().done, 1);
+}
+
+#[test]
+fn subtasks_stay_open_when_their_blocked_parent_fails() {
+    let store = store();
+    let board = store.add_board("work").unwrap();
+    let parent = store.add_task(&new_task("release", board)).unwrap();
+    let subtask = store
+        .add_task(&NewTask {
+            parent_id: Some(parent),
+            ..new_task("changelog", board)
+        })
+        .unwrap();
+    let blocker = store.add_task(&new_task("freeze", board)).unwrap();
+    store.add_dependency(parent, blocker).unwrap();
+
+    assert!(complete_with_subtasks(&store, parent).is_err());
+    assert_eq!(store.get_task(subtask).unwrap().done, 0);
+
+    set_task_done(&store, blocker, true).unwrap();
+    assert_eq!(complete_with_subtasks(&store, parent).unwrap(), (1, None));
+    assert_eq!(store.get_task(parent).unwrap().done, 1);
+}
+
+#[test]
+fn deleting_a_task_deletes_its_subtasks() {
+    let store = store();
+    let board = store.add_board("work").unwrap();
+    let parent = store.add_task(&new_task("release", board)).unwrap();
+    let child = store
+        .add_task(&NewTask {
+            parent_id: Some(parent),
+            ..new_task("build", board)
+        })
+        .unwrap();
+    let grandchild = store
+        .add_task(&NewTask {
+            parent_id: Some(child),
+            ..new_task("compile", board)
+        })
+        .unwrap();
+    store
+        .add_comment(grandchild, "warnings everywhere")
+        .unwrap();
+
+    store.delete_task(parent).unwrap();
+
+    assert_eq!(store.count_records(TABLE_TASKS).unwrap(), 0);
+    assert!(store.list_comments(grandchild).unwrap().is_empty());
+    assert!(store.search("warnings", 10).unwrap().is_empty());
+}
+
+#[test]
+fn task_cannot_be_its_own_ancestor() {
+    let store = store();
+    let board = store.add_board("work").unwrap();
+    let parent = store.add_task(&new_task("release", board)).unwrap();
+    let child = store
+        .add_task(&NewTask {
+            parent_id: Some(parent),
+            ..new_task("build", board)
+        })
+        .unwrap();
+
+    let mut task = store.get_task(parent).unwrap();
+    task.parent_id = Some(child);
+    assert!(matches!(
+        store.update_task(&task),
+        Err(Error::Validation(_))
+    ));
+    task.parent_id = Some(parent);
+    assert!(matches!(
+        store.update_task(&task),
+        Err(Error::Validation(_))
+    ));
+
+    let missing = NewTask {
+        parent_id: Some(42),
+        ..new_task("lost", board)
+    };
+    assert!(matches!(store.add_task(&missing), Err(Error::NotFound(_))));
+}
+
+#[test]
+fn blocked_tasks_wait_for_their_blockers() {
+    let store = store();
+    let board = store.add_board("work").unwrap();
+    let design = store.add_task(&new_task("design", board)).unwrap();
+    let build = store.add_task(&new_task("build", board)).unwrap();
+    let ship = store.add_task(&new_task("ship", board)).unwrap();
+    store.add_dependency(build, design).unwrap();
+    store.add_dependency(ship, build).unwrap();
+
+    assert_eq!(store.get_task(build).unwrap().blockers, 1);
+    let ready: Vec<u16> = get_ready_tasks(&store)
+        .unwrap()
+        .iter()
+        .map(|x| x.id)
+        .collect();
+    assert_eq!(ready, [design]);
+    let result = set_task_done(&store, build, true);
+    assert!(matches!(result, Err(Error::Validation(_))));
+
+    set_task_done(&store, design, true).unwrap();
+    assert_eq!(store.get_task(build).unwrap().blockers, 0);
+    set_task_done(&store, build, true).unwrap();
+    let ready: Vec<u16> = get_ready_tasks(&store)
+        .unwrap()
+        .iter()
+        .map(|x| x.id)
+        .collect();
+    assert_eq!(ready, [ship]);
+}
+
+#[test]
+fn dependency_cycles_are_refused() {
+    let store = store();
+    let board = store.add_board("work").unwrap();
+    let a = store.add_task(&new_task("a", board)).unwrap();
+    let b = store.add_task(&new_task("b", board)).unwrap();
+    let c = store.add_task(&new_task("c", board)).unwrap();
+    store.add_dependency(b, a).unwrap();
+    store.add_dependency(c, b).unwrap();
+
+    for (task, blocker) in [(a, c), (a, b), (a, a)] {
+        assert!(matches!(
+            store.add_dependency(task, blocker),
+            Err(Error::Validation(_))
+        ));
+    }
+
+    store.remove_dependency(c, b).unwrap();
+    store.add_dependency(b, c).unwrap();
+    store.delete_task(a).unwrap();
+    assert!(store.list_blockers(b).unwrap().iter().all(|x| x.id == c));
+}
+
+#[test]
+fn tags_are_renamed_merged_and_deleted() {
+    let store = store();
+    let board = store.add_board("work").unwrap();
+    let report = store.add_task(&new_task("report", board)).unwrap();
+    let invoice = store.add_task(&new_task("invoice", board)).unwrap();
+    let tags = |names: &[&str]| names.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+    store
+        .set_task_tags(report, &tags(&["urgent", "office"]))
+        .unwrap();
+    store
+        .set_task_tags(invoice, &tags(&["asap", "office"]))
+        .unwrap();
+    assert_eq!(store.get_task(report).unwrap().tags, ["office", "urgent"]);
+
+    let asap = store.get_tag("ASAP").unwrap();
+    let urgent = store.get_tag("urgent").unwrap();
+    assert!(matches!(
+        store.rename_tag(asap.id, "Urgent"),
+        Err(Error::Validation(_))
+    ));
+    store.merge_tags(asap.id, urgent.id).unwrap();
+    assert_eq!(store.get_task(invoice).unwrap().tags, ["office", "urgent"]);
+    assert_eq!(store.get_tag("urgent").unwrap().tasks, 2);
+
+    let office = store.get_tag("office").unwrap();
+    store.rename_tag(office.id, "work").unwrap();
+    store.delete_tag(urgent.id).unwrap();
+    assert_eq!(store.get_task(report).unwrap().tags, ["work"]);
+    let names: Vec<String> = store
+        .list_tags()
+        .unwrap()
+        .into_iter()
+        .map(|x| x.name)
+        .collect();
+    assert_eq!(names, ["work"]);
+}
+
+#[test]
+fn done_flag_follows_the_terminal_column() {
+    let store = store();
+    let board = store.add_board("work").unwrap();
+    let titles = |board| {
+        store
+            .list_columns(board)
+            .unwrap()
+            .into_iter()
+            .map(|x| x.title)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(titles(board), ["Todo", "Doing", "Done"]);
+    let review = store.add_column(board, "Review").unwrap();
+    assert_eq!(titles(board), ["Todo", "Doing", "Review", "Done"]);
+
+    let id = store.add_task(&new_task("report", board)).unwrap();
+    assert_eq!(store.get_task(id).unwrap().column, "Todo");
+
+    set_task_column(&store, id, review).unwrap();
+    let task = store.get_task(id).unwrap();
+    assert_eq!((task.column.as_str(), task.done), ("Review", 0));
+
+    set_task_done(&store, id, true).unwrap();
+    let task = store.get_task(id).unwrap();
+    assert_eq!((task.column.as_str(), task.done), ("Done", 1));
+
+    //The old terminal column becomes a regular one, reopening its tasks
+    store.set_terminal_column(review).unwrap();
+    assert_eq!(store.get_task(id).unwrap().done, 0);
+    set_task_done(&store, id, true).unwrap();
+    assert_eq!(store.get_task(id).unwrap().column, "Review");
+}
+
+#[test]
+fn columns_are_reordered_and_deleted() {
+    let store = store();
+    let board = store.add_board("work").unwrap();
+    let columns = store.list_columns(board).unwrap();
+    let (todo, doing, done) = (columns[0].id, columns[1].id, columns[2].id);
+    let id = store
+        .add_task(&NewTask {
+            column_id: Some(doing),
+            ..new_task("report", board)
+        })
+        .unwrap();
+
+    store.move_column(doing, 0).unwrap();
+    let order: Vec<u16> = store
+        .list_columns(board)
+        .unwrap()
+        .iter()
+        .map(|x| x.id)
+        .collect();
+    assert_eq!(order, [doing, todo, done]);
+
+    assert!(matches!(
+        store.delete_column(done),
+        Err(Error::Validation(_))
+    ));
+    store.delete_column(doing).unwrap();
+    assert_eq!(store.get_task(id).unwrap().column_id, Some(todo));
+    assert!(matches!(
+        store.delete_column(todo),
+        Err(Error::Validation(_))
+    ));
+
+    //Moving to another board picks its first column
+    let home = store.add_board("home").unwrap();
+    let mut task = store.get_task(id).unwrap();
+    task.board_id = home;
+    store.update_task(&task).unwrap();
+    assert_eq!(
+        store.get_task(id).unwrap().column_id,
+        Some(store.list_columns(home).unwrap()[0].id)
+    );
+
+    store.delete_board(home).unwrap();
+    assert_eq!(store.count_records(TABLE_TASKS).unwrap(), 0);
+}
+
+#[test]
+fn completion_time_follows_status() {
+    let store = store();
+    let board = store.add_board("work").unwrap();
+    let id = store.add_task(&new_task("report", board)).unwrap();
+    assert_eq!(store.get_task(id).unwrap().completed_at, None);
+
+    set_task_done(&store, id, true).unwrap();
+    let completed_at = store.get_task(id).unwrap().completed_at.unwrap();
+    assert!(Utc::now() - completed_at < Duration::minutes(1));
+
+    //Editing a done task keeps its completion time
+    let mut task = store.get_task(id).unwrap();
+    task.title = "final report".to_string();
+    store.update_task(&task).unwrap();
+    assert_eq!(store.get_task(id).unwrap().completed_at, Some(completed_at));
+
+    set_task_done(&store, id, false).unwrap();
+    assert_eq!(store.get_task(id).unwrap().completed_at, None);
+
+    //Tasks of a column becoming terminal are completed
+    let doing = store.list_columns(board).unwrap()[1].id;
+    set_task_column(&store, id, doing).unwrap();
+    store.set_terminal_column(doing).unwrap();
+    let task = store.get_task(id).unwrap();
+    assert_eq!(task.done, 1);
+    assert!(task.completed_at.is_some());
+}
+
+#[test]
+fn history_records_every_change() {
+    let store = store();
+    let board = store.add_board("work").unwrap();
+    let id = store.add_task(&new_task("report", board)).unwrap();
+    let subtask = store
+        .add_task(&NewTask {
+            parent_id: Some(id),
+            ..new_task("draft", board)
+        })
+        .unwrap();
+
+    let mut task = store.get_task(id).unwrap();
+    task.title = "final report".to_string();
+    task.priority = Priority::High;
+    store.update_task(&task).unwrap();
+    store.set_task_tags(id, &["q3".to_string()]).unwrap();
+    let reminder = store
+        .add_reminder(id, ReminderTime::At(Utc.ymd(2030, 1, 1).and_hms(9, 0, 0)))
+        .unwrap();
+    store.delete_reminder(reminder).unwrap();
+    set_task_done(&store, subtask, true).unwrap();
+    set_task_done(&store, id, true).unwrap();
+    store.delete_task(id).unwrap();
+
+    let history = store.list_history(id).unwrap();
+    let changes: Vec<(HistoryAction, &str, Option<&str>, Option<&str>)> = history
+        .iter()
+        .map(|x| {
+            (
+                x.action,
+                x.field.as_str(),
+                x.old_value.as_deref(),
+                x.new_value.as_deref(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        changes,
+        [
+            (HistoryAction::Created, "", None, Some("report")),
+            (
+                HistoryAction::Edited,
+                "title",
+                Some("report"),
+                Some("final report")
+            ),
+            (
+                HistoryAction::Edited,
+                "priority",
+                Some("normal"),
+                Some("high")
+            ),
+            (HistoryAction::Edited, "tags", Some(""), Some("q3")),
+            (
+                HistoryAction::Reminder,
+                "reminder",
+                None,
+                Some("2030-01-01 09:00:00 UTC")
+            ),
+            (
+                HistoryAction::Reminder,
+                "reminder",
+                Some("2030-01-01 09:00:00 UTC"),
+                None
+            ),
+            (HistoryAction::Edited, "column", Some("Todo"), Some("Done")),
+            (
+                HistoryAction::Status,
+                "status",
+                Some("pending"),
+                Some("done")
+            ),
+            (HistoryAction::Deleted, "", Some("final report"), None),
+        ]
+    );
+
+    //Cascaded deletes are recorded too
+    let last = store.list_history(subtask).unwrap().pop().unwrap();
+    assert_eq!(last.action, HistoryAction::Deleted);
+}
+
+#[test]
+fn deleted_task_ids_are_not_reused() {
+    let store = store();
+    let board = store.add_board("work").unwrap();
+    let first = store.add_task(&new_task("first", board)).unwrap();
+    store.delete_task(first).unwrap();
+    let second = store.add_task(&new_task("second", board)).unwrap();
+    assert_ne!(second, first);
+
+    let history = store.list_history(first).unwrap();
+    assert_eq!(history.last().unwrap().action, HistoryAction::Deleted);
+    assert_eq!(store.list_history(second).unwrap().len(), 1);
+}
+
+#[test]
+fn task_ids_continue_after_the_history() {
+    let conn = rusqlite::Connection::open_in_memory().unwrap();
+    let latest = migrations::latest_version() as usize;
+    for migration in &migrations::MIGRATIONS[..latest - 1] {
+        (migration.up)(&conn).unwrap();
+    }
+    //Task 3 was the latest, and has been deleted
+    conn.execute_batch(&format!(
+        "PRAGMA user_version = {};
+        INSERT INTO boards (title) VALUES ('work');
+        INSERT INTO tasks (id, title, done, board_id, created_at) VALUES (1, 'report', 0, 1, '2026-10-18T08:00:00Z');
+        INSERT INTO history (task_id, action, created_at) VALUES (3, 'deleted', '2026-10-18T09:00:00Z');",
+        latest - 1
+    ))
+    .unwrap();
+
+    let store = SqliteStore::new(conn).unwrap();
+    assert_eq!(store.get_task(1).unwrap().title, "report");
+    let board = store.add_board("home").unwrap();
+    assert_eq!(store.add_task(&new_task("memo", board)).unwrap(), 4);
+    assert_eq!(store.search("memo", 10).unwrap().len(), 1);
+}
+
+#[test]
+fn monthly_series_returns_to_its_day_after_short_months() {
+    let store = store();
+    let board = store.add_board("home").unwrap();
+    let due_at = Local.ymd(2030, 1, 31).and_hms(9, 0, 0).with_timezone(&Utc);
+    let mut id = store
+        .add_task(&NewTask {
+            due_at: Some(due_at),
+            recurrence: Some("monthly".parse().unwrap()),
+            ..NewTask::new("pay rent", board)
+        })
+        .unwrap();
+
+    let mut days = Vec::new();
+    for _ in 0..2 {
+        id = set_task_done(&store, id, true).unwrap().unwrap();
+        let due_at = store.get_task(id).unwrap().due_at.unwrap();
+        days.push(due_at.with_timezone(&Local).format("%m-%d").to_string());
+    }
+    assert_eq!(days, ["02-28", "03-31"]);
+}
+
+#[test]
+fn exhausted_task_ids_fail_instead_of_wrapping() {
+    let store = store();
+    let board = store.add_board("work").unwrap();
+    store.add_task(&new_task("first", board)).unwrap();
+    store
+        .connection()
+        .execute(
+            "UPDATE sqlite_sequence SET seq = 65535 WHERE name = 'tasks'",
+            [],
+        )
+        .unwrap();
+
+    let result = store.add_task(&new_task("second", board));
+    assert!(matches!(result, Err(Error::Validation(_))));
+    assert_eq!(store.count_records(TABLE_TASKS).unwrap(), 1);
+    assert_eq!(store.list_history(1).unwrap().len(), 1);
+}